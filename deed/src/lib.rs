@@ -3,11 +3,13 @@ use near_sdk::{
     near_bindgen,
     ext_contract,
     borsh::{self, BorshDeserialize, BorshSerialize},
-    json_types::{ ValidAccountId, Base58PublicKey },
+    serde::{Deserialize, Serialize},
+    json_types::{ ValidAccountId, Base58PublicKey, Base64VecU8, U128 },
     env,
     Promise,
     PromiseResult,
     PanicOnDefault,
+    PublicKey,
     log,
 };
 
@@ -16,8 +18,10 @@ near_sdk::setup_alloc!();
 // TODO: Adjust these to minimums
 const DEED_STORAGE_COST: u128 = 1_700_000_000_000_000_000_000_000;
 const ESCROW_STORAGE_COST: u128 = 2_000_000_000_000_000_000_000;
-const REGISTER_GAS_FEE: u64 = 5_000_000_000_000; // 5 Tgas
+// escrow checks is_swept back on this deed before registering it
+const REGISTER_GAS_FEE: u64 = 30_000_000_000_000; // 30 Tgas
 const CALLBACK_GAS_FEE: u64 = 20_000_000_000_000; // 20 Tgas
+const SWEEP_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000 + REGISTER_GAS_FEE; // 40 Tgas
 
 #[ext_contract(ext_escrow)]
 pub trait ExtEscrow {
@@ -26,7 +30,47 @@ pub trait ExtEscrow {
 
 #[ext_contract(ext_self)]
 pub trait ExtSelf {
-    fn ownership_callback(&mut self, original_owner: AccountId);
+    fn sweep_callback(&mut self, key_count: u32);
+    fn ownership_callback(&mut self) -> bool;
+    fn lease_callback(&mut self) -> bool;
+    fn remove_lease_callback(&mut self);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DeedStatus {
+    /// Held by escrow, no ownership change requested yet
+    Escrowed,
+    /// Escrow authorized an account to claim this deed with a key of their choice
+    Claimable,
+    /// Held for a claimant while escrow settles a lot, not claimable until escrow confirms
+    Reserved,
+    /// New owner key is being added
+    Pending,
+    /// New owner key confirmed
+    Transferred,
+    /// Last ownership change failed, previous underwriter was restored
+    Failed,
+}
+
+/// Restricts an access key to calling specific methods on a single contract
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FunctionCallPermission {
+    pub receiver_id: AccountId,
+    /// Empty list allows any method on the receiver
+    pub method_names: Vec<String>,
+    /// None for unlimited allowance
+    pub allowance: Option<U128>,
+}
+
+/// Additional key granted to the new owner alongside their full access key
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccessKey {
+    pub public_key: Base58PublicKey,
+    /// None for a full access key
+    pub permission: Option<FunctionCallPermission>,
 }
 
 #[near_bindgen]
@@ -34,6 +78,20 @@ pub trait ExtSelf {
 pub struct Deed {
     escrow: AccountId,
     underwriter: AccountId,
+
+    // set once the init key sweep succeeded, escrow only registers after that
+    is_swept: bool,
+
+    // two-phase ownership change, restored on failure
+    status: DeedStatus,
+    pending_owner: Option<PublicKey>,
+    previous_underwriter: Option<AccountId>,
+
+    // account authorized by escrow to claim this deed via "claim"
+    claimant: Option<AccountId>,
+
+    // time-bounded key added by escrow for a renter, removed again by escrow on expiry
+    lease_key: Option<PublicKey>,
 }
 
 /// Deed
@@ -43,57 +101,204 @@ impl Deed {
     /// Upon deploy, contract initializes with only escrow account owning this account
     /// the account is available for any other ownership transfers
     ///
+    /// `keys` MUST list every access key on this account, including the key signing this deploy.
+    /// All of them get deleted in a single batch, if any key is unknown the whole sweep fails
+    /// and the account is never registered with escrow. Remaining balance is only sent to the
+    /// underwriter once the sweep succeeded.
+    ///
     /// ```bash
-    /// near deploy --wasmFile res/deed.wasm --initFunction new --initArgs '{"escrow": "escrow.testnet", "underwriter": "ACCOUNT_THAT_WILL_OWN.testnet"}' --accountId ACCOUNT_THAT_WILL_OWN.testnet --gas 300000000000000
+    /// near deploy --wasmFile res/deed.wasm --initFunction new --initArgs '{"escrow": "escrow.testnet", "underwriter": "ACCOUNT_THAT_WILL_OWN.testnet", "keys": ["ed25519:Ggs1UC1z..."]}' --accountId ACCOUNT_THAT_WILL_OWN.testnet --gas 300000000000000
     /// ```
     #[init(ignore_state)]
     #[payable]
     pub fn new(
         underwriter: ValidAccountId,
         escrow: ValidAccountId,
+        keys: Vec<Base58PublicKey>,
     ) -> Self {
         assert_eq!(env::signer_account_id(), env::current_account_id(), "Signer must have original ownership");
         assert_eq!(env::predecessor_account_id(), env::current_account_id(), "Signer must have original ownership");
 
-        // transfer any remaining balance to underwriter
-        // transfers ALL balance except whats needed for contract storage
-        let remaining_balance = core::cmp::max(env::account_balance() - DEED_STORAGE_COST - ESCROW_STORAGE_COST, 0);
-        log!("remaining_balance {}", &remaining_balance);
-        Promise::new(underwriter.to_string())
-            .transfer(remaining_balance);
-
-        // register with escrow contract
-        ext_escrow::register(
-            underwriter.to_string(),
-            &escrow.to_string(),
-            ESCROW_STORAGE_COST,
-            REGISTER_GAS_FEE,
+        let mut swept_keys: Vec<PublicKey> = keys.into_iter().map(|k| k.into()).collect();
+        swept_keys.sort();
+        swept_keys.dedup();
+        assert!(swept_keys.contains(&env::signer_account_pk()), "Keys must include the signer key");
+
+        // remove every key, so escrow ends up as the sole controller
+        let mut sweep = Promise::new(env::current_account_id());
+        for pk in swept_keys.iter() {
+            sweep = sweep.delete_key(pk.clone());
+        }
+
+        // pay out & register with escrow contract, only once the sweep succeeded
+        sweep.then(
+            ext_self::sweep_callback(
+                swept_keys.len() as u32,
+                &env::current_account_id(),
+                0,
+                SWEEP_CALLBACK_GAS_FEE,
+            )
         );
 
         Deed {
             escrow: escrow.to_string(),
             underwriter: underwriter.to_string(),
+            is_swept: false,
+            status: DeedStatus::Escrowed,
+            pending_owner: None,
+            previous_underwriter: None,
+            claimant: None,
+            lease_key: None,
         }
     }
 
+    /// Internal function to check all keys were removed, then pay out & register with escrow
+    #[private]
+    pub fn sweep_callback(&mut self, key_count: u32) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                self.is_swept = true;
+                log!("Keys swept: {}", key_count);
+
+                // transfer any remaining balance to underwriter
+                // transfers ALL balance except whats needed for contract storage
+                let remaining_balance = env::account_balance()
+                    .saturating_sub(DEED_STORAGE_COST + ESCROW_STORAGE_COST);
+                log!("remaining_balance {}", &remaining_balance);
+                if remaining_balance > 0 {
+                    Promise::new(self.underwriter.clone())
+                        .transfer(remaining_balance);
+                }
+
+                ext_escrow::register(
+                    self.underwriter.clone(),
+                    &self.escrow,
+                    ESCROW_STORAGE_COST,
+                    REGISTER_GAS_FEE,
+                );
+            }
+            PromiseResult::Failed => {
+                // batch actions are atomic, no key was removed
+                log!("Key sweep failure, not registering with escrow");
+            }
+            PromiseResult::NotReady => unreachable!(),
+        };
+    }
+
     /// Adding access keys for escrow mediated keys
     /// IMPORTANT: pk MUST be the pk of the claimer's signing keys, otherwise they wont be able to own it!
+    /// Optional extra_keys are added in the same batch, all keys get added or none do.
     ///
     /// ```bash
-    /// near call escrow.testnet claim '{"pk": "ed25519:Ggs1UC1z..."}' --accountId ACCOUNT_THAT_OWNS.testnet --gas 300000000000000
+    /// near call escrow.testnet claim '{"pk": "ed25519:Ggs1UC1z...", "extra_keys": [{"public_key": "ed25519:2mXm...", "permission": {"receiver_id": "app.testnet", "method_names": ["play"], "allowance": "250000000000000000000000"}}]}' --accountId ACCOUNT_THAT_OWNS.testnet --gas 300000000000000
     /// ```
-    pub fn change_ownership(&mut self, pk: Base58PublicKey) -> Promise {
+    pub fn change_ownership(&mut self, pk: Base58PublicKey, extra_keys: Option<Vec<AccessKey>>) -> Promise {
         assert_eq!(env::predecessor_account_id(), self.escrow.to_string(), "Unauthorized access, escrow only");
+        assert!(
+            self.status == DeedStatus::Escrowed || self.status == DeedStatus::Failed,
+            "Ownership change not available"
+        );
+        assert!(self.lease_key.is_none(), "Deed is leased");
 
-        // Remove underwriter so escrow is the sole executor of the account temporarily
+        // Remember who to restore, and remove underwriter so escrow is the sole executor of the account temporarily
+        self.previous_underwriter = Some(self.underwriter.clone());
         self.underwriter = AccountId::default();
 
-        // Add new access key
-        Promise::new(env::current_account_id())
-            .add_full_access_key(pk.into())
+        self.add_owner_keys(pk, extra_keys.unwrap_or_default())
+    }
+
+    /// Authorize an account to claim this deed, instead of escrow supplying the new key.
+    /// The claimant then calls "claim" directly with a key of their choice.
+    ///
+    /// ```bash
+    /// near call escrow.testnet close_escrow_to_account '{"title": "ACCOUNT_THAT_OWNS.testnet", "claimant": "winner.testnet"}' --accountId ACCOUNT_THAT_OWNS.testnet --gas 300000000000000
+    /// ```
+    pub fn set_claimant(&mut self, claimant: ValidAccountId) {
+        assert_eq!(env::predecessor_account_id(), self.escrow.to_string(), "Unauthorized access, escrow only");
+        assert!(
+            self.status == DeedStatus::Escrowed || self.status == DeedStatus::Failed,
+            "Ownership change not available"
+        );
+        assert!(self.lease_key.is_none(), "Deed is leased");
+
+        self.previous_underwriter = Some(self.underwriter.clone());
+        self.underwriter = AccountId::default();
+        self.claimant = Some(claimant.to_string());
+        self.status = DeedStatus::Claimable;
+        log!("Claimant set: {}", claimant);
+    }
+
+    /// Reserve this deed for a claimant, as part of a lot sold to one winner.
+    /// Escrow confirms (making it claimable) or releases it via "settle_reservation",
+    /// once every deed in the lot answered.
+    ///
+    /// ```bash
+    /// near call escrow.testnet close_escrow_lot '{"titles": ["ACCOUNT_THAT_OWNS.testnet", "OTHER.testnet"], "winner": "winner.testnet"}' --accountId registrar.testnet --gas 300000000000000
+    /// ```
+    pub fn reserve(&mut self, claimant: ValidAccountId) {
+        assert_eq!(env::predecessor_account_id(), self.escrow.to_string(), "Unauthorized access, escrow only");
+        assert!(
+            self.status == DeedStatus::Escrowed || self.status == DeedStatus::Failed,
+            "Ownership change not available"
+        );
+        assert!(self.lease_key.is_none(), "Deed is leased");
+
+        self.previous_underwriter = Some(self.underwriter.clone());
+        self.underwriter = AccountId::default();
+        self.claimant = Some(claimant.to_string());
+        self.status = DeedStatus::Reserved;
+        log!("Reserved: {}", claimant);
+    }
+
+    /// Confirm a reservation, so the claimant can claim, or release it restoring the previous underwriter
+    pub fn settle_reservation(&mut self, confirmed: bool) {
+        assert_eq!(env::predecessor_account_id(), self.escrow.to_string(), "Unauthorized access, escrow only");
+        assert_eq!(self.status, DeedStatus::Reserved, "Deed is not reserved");
+
+        if confirmed {
+            self.status = DeedStatus::Claimable;
+            log!("Reservation confirmed");
+        } else {
+            self.underwriter = self.previous_underwriter.take().unwrap_or_default();
+            self.claimant = None;
+            self.status = DeedStatus::Escrowed;
+            log!("Reservation released");
+        }
+    }
+
+    /// Claim ownership as the account authorized by escrow
+    /// IMPORTANT: pk MUST be a key the claimant controls, otherwise they wont be able to own it!
+    /// Optional extra_keys are added in the same batch, same as "change_ownership"
+    ///
+    /// ```bash
+    /// near call ACCOUNT_THAT_OWNS.testnet claim '{"pk": "ed25519:Ggs1UC1z..."}' --accountId winner.testnet --gas 300000000000000
+    /// ```
+    pub fn claim(&mut self, pk: Base58PublicKey, extra_keys: Option<Vec<AccessKey>>) -> Promise {
+        assert_eq!(self.status, DeedStatus::Claimable, "Deed is not claimable");
+        assert_eq!(
+            Some(env::predecessor_account_id()),
+            self.claimant,
+            "Unauthorized access, claimant only"
+        );
+
+        self.add_owner_keys(pk, extra_keys.unwrap_or_default())
+    }
+
+    /// Add the new owner keys as one batch, confirmed in "ownership_callback"
+    fn add_owner_keys(&mut self, pk: Base58PublicKey, extra_keys: Vec<AccessKey>) -> Promise {
+        self.pending_owner = Some(pk.clone().into());
+        self.status = DeedStatus::Pending;
+
+        // Add new access keys
+        let mut batch = Promise::new(env::current_account_id())
+            .add_full_access_key(pk.into());
+        for key in extra_keys {
+            batch = add_key(batch, key);
+        }
+
+        batch
             .then(
                 ext_self::ownership_callback(
-                    self.underwriter.clone(),
                     &env::current_account_id(),
                     0,
                     CALLBACK_GAS_FEE,
@@ -102,38 +307,206 @@ impl Deed {
     }
 
     /// Internal function to check that the key change was successful
+    /// Returns whether the new owner keys were added, so escrow can settle accordingly
     #[private]
-    pub fn ownership_callback(&mut self, original_owner: AccountId) {
-        match env::promise_result(0) {
+    pub fn ownership_callback(&mut self) -> bool {
+        let transferred = match env::promise_result(0) {
             PromiseResult::Successful(_) => {
-                // NOTE: this contract could be removed now.
+                // NOTE: new owner can now call "cleanup" to remove this contract
+                self.status = DeedStatus::Transferred;
+                self.previous_underwriter = None;
+                self.claimant = None;
                 log!("Owner transfer success");
+                true
             }
             PromiseResult::Failed => {
-                // reset owner if unsuccessful
-                self.underwriter = original_owner;
+                if self.claimant.is_some() {
+                    // claimant can retry with another key
+                    self.status = DeedStatus::Claimable;
+                } else {
+                    // reset owner if unsuccessful
+                    self.underwriter = self.previous_underwriter.take().unwrap_or_default();
+                    self.status = DeedStatus::Failed;
+                }
                 log!("Owner transfer failure");
+                false
             }
             PromiseResult::NotReady => unreachable!(),
         };
+        self.pending_owner = None;
+        transferred
+    }
+
+    /// Grant a renter a time-bounded function call key, escrow removes it again via "remove_lease_key".
+    /// Only one lease at a time, ownership cannot change while leased.
+    ///
+    /// ```bash
+    /// near call escrow.testnet rent '{"title": "ACCOUNT_THAT_OWNS.testnet", "public_key": "ed25519:Ggs1UC1z..."}' --accountId renter.testnet --amount 1 --gas 300000000000000
+    /// ```
+    pub fn add_lease_key(&mut self, key: AccessKey) -> Promise {
+        assert_eq!(env::predecessor_account_id(), self.escrow.to_string(), "Unauthorized access, escrow only");
+        assert!(
+            self.status == DeedStatus::Escrowed || self.status == DeedStatus::Failed,
+            "Lease not available"
+        );
+        assert!(self.lease_key.is_none(), "Deed is leased");
+        // A full access key, or one calling this account, could remove escrow's control
+        let receiver_id = key.permission.as_ref().map(|p| p.receiver_id.clone()).expect("Lease key must be a function call key");
+        assert_ne!(receiver_id, env::current_account_id(), "Lease key cannot call this account");
+
+        self.lease_key = Some(key.public_key.clone().into());
+        add_key(Promise::new(env::current_account_id()), key)
+            .then(
+                ext_self::lease_callback(
+                    &env::current_account_id(),
+                    0,
+                    CALLBACK_GAS_FEE,
+                )
+            )
+    }
+
+    /// Internal function to check the lease key was added, cleared if not
+    /// Returns whether the key was added, so escrow can settle the rent accordingly
+    #[private]
+    pub fn lease_callback(&mut self) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                log!("Lease key added");
+                true
+            }
+            PromiseResult::Failed => {
+                self.lease_key = None;
+                log!("Lease key failure");
+                false
+            }
+            PromiseResult::NotReady => unreachable!(),
+        }
+    }
+
+    /// Remove the renter key, returning sole control to escrow.
+    /// The key is only forgotten in "remove_lease_callback", so a failed removal can be retried.
+    ///
+    /// ```bash
+    /// near call escrow.testnet end_lease '{"title": "ACCOUNT_THAT_OWNS.testnet"}' --accountId anyone.testnet --gas 300000000000000
+    /// ```
+    pub fn remove_lease_key(&mut self) -> Promise {
+        assert_eq!(env::predecessor_account_id(), self.escrow.to_string(), "Unauthorized access, escrow only");
+        let pk = self.lease_key.clone().expect("Deed is not leased");
+
+        Promise::new(env::current_account_id())
+            .delete_key(pk)
+            .then(
+                ext_self::remove_lease_callback(
+                    &env::current_account_id(),
+                    0,
+                    CALLBACK_GAS_FEE,
+                )
+            )
+    }
+
+    /// Internal function to clear the lease key once it is gone.
+    /// Deleting a key from this account only fails when the key no longer exists,
+    /// which counts as removed.
+    #[private]
+    pub fn remove_lease_callback(&mut self) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => log!("Lease key removed"),
+            PromiseResult::Failed => log!("Lease key already removed"),
+            PromiseResult::NotReady => unreachable!(),
+        };
+        self.lease_key = None;
+    }
+
+    /// Once ownership has transferred, the new owner can remove all deed state
+    /// and replace this contract with an empty one, or their own contract code.
+    /// Must be signed by the account itself, using the key added via `change_ownership`
+    ///
+    /// ```bash
+    /// near call ACCOUNT_THAT_OWNS.testnet cleanup '{"code": null}' --accountId ACCOUNT_THAT_OWNS.testnet --gas 300000000000000
+    /// ```
+    pub fn cleanup(&self, code: Option<Base64VecU8>) -> Promise {
+        assert_eq!(env::predecessor_account_id(), env::current_account_id(), "Must be called by the new owner");
+        assert_eq!(self.status, DeedStatus::Transferred, "Ownership has not been transferred");
+
+        // Wipe deed state, this is a view-like fn so state wont be written back
+        env::storage_remove(b"STATE");
+        log!("Deed removed");
+
+        // Replace the deed code, empty code leaves a clean account
+        let code: Vec<u8> = code.map(|c| c.into()).unwrap_or_default();
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+    }
+
+    /// Returns the current ownership status, the underwriter and any pending owner key
+    ///
+    /// ```bash
+    /// near view ACCOUNT_THAT_OWNS.testnet get_status
+    /// ```
+    pub fn get_status(&self) -> (DeedStatus, AccountId, Option<Base58PublicKey>) {
+        (
+            self.status.clone(),
+            self.underwriter.clone(),
+            self.pending_owner.clone().map(Base58PublicKey),
+        )
+    }
+
+    /// Returns the key currently leased out by escrow, if any
+    ///
+    /// ```bash
+    /// near view ACCOUNT_THAT_OWNS.testnet get_lease_key
+    /// ```
+    pub fn get_lease_key(&self) -> Option<Base58PublicKey> {
+        self.lease_key.clone().map(Base58PublicKey)
+    }
+
+    /// Returns whether the init key sweep succeeded.
+    /// Contracts cannot list their own access keys, so this only confirms the listed keys are gone:
+    /// `near keys` for this account must come back empty (apart from keys escrow added later)
+    /// to know no other keys remain.
+    ///
+    /// ```bash
+    /// near view ACCOUNT_THAT_OWNS.testnet is_swept
+    /// ```
+    pub fn is_swept(&self) -> bool {
+        self.is_swept
+    }
+}
+
+/// Adds a full access or function call key to the batch
+fn add_key(batch: Promise, key: AccessKey) -> Promise {
+    match key.permission {
+        Some(FunctionCallPermission { receiver_id, method_names, allowance }) => {
+            batch.add_access_key(
+                key.public_key.into(),
+                allowance.map(|a| a.0).unwrap_or(0),
+                receiver_id,
+                method_names.join(",").into_bytes(),
+            )
+        }
+        None => batch.add_full_access_key(key.public_key.into()),
     }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use std::convert::TryFrom;
-    use near_sdk::{test_utils::{accounts, VMContextBuilder}};
+    use near_sdk::test_utils::{accounts, testing_env_with_promise_results, VMContextBuilder};
     use near_sdk::json_types::{ValidAccountId};
     use near_sdk::MockedBlockchain;
     use near_sdk::{testing_env};
 
     use super::*;
 
+    fn signer_pk() -> Base58PublicKey {
+        Base58PublicKey::try_from("ed25519:6Mzi9dRMSiPWYp7BgLJ2Lj6KPCcs48FwB93NgQ4LKSBo".to_string()).unwrap()
+    }
+
     fn create_blank_account_manager() -> Deed {
         Deed::new(
             accounts(1),
             accounts(0),
-            Some(true)
+            vec![signer_pk()]
         )
     }
 
@@ -145,26 +518,268 @@ mod tests {
         builder
             .current_account_id(c)
             .signer_account_id(s)
+            .signer_account_pk(signer_pk().into())
             .predecessor_account_id(p);
         builder
     }
 
     #[test]
     fn test_init() {
-        let context = get_context(accounts(1), accounts(1), accounts(0));
+        let context = get_context(accounts(1), accounts(1), accounts(1));
         testing_env!(context.build());
         let contract = create_blank_account_manager();
         assert_eq!(contract.escrow, accounts(0).to_string());
     }
 
+    #[test]
+    #[should_panic(expected = "Keys must include the signer key")]
+    fn test_init_missing_signer_key() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        Deed::new(
+            accounts(1),
+            accounts(0),
+            vec![Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_sweep_callback() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = Deed::new(
+            accounts(1),
+            accounts(0),
+            vec![signer_pk(), signer_pk()]
+        );
+        assert!(!contract.is_swept(), "Sweep is only confirmed in callback");
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.sweep_callback(1);
+        assert!(!contract.is_swept(), "Failed sweep must not register");
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(vec![]));
+        contract.sweep_callback(1);
+        assert!(contract.is_swept());
+    }
+
     #[test]
     fn test_transfer_ownership() {
-        let context = get_context(accounts(1), accounts(1), accounts(0));
+        let context = get_context(accounts(1), accounts(1), accounts(1));
         testing_env!(context.build());
         let mut contract = create_blank_account_manager();
 
-        contract.change_ownership(Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap());
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+
+        contract.change_ownership(Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap(), None);
 
         assert_eq!(contract.escrow, accounts(0).to_string());
+        assert_eq!(contract.get_status().0, DeedStatus::Pending);
+    }
+
+    #[test]
+    fn test_transfer_ownership_extra_keys() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.change_ownership(
+            Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap(),
+            Some(vec![AccessKey {
+                public_key: signer_pk(),
+                permission: Some(FunctionCallPermission {
+                    receiver_id: accounts(3).to_string(),
+                    method_names: vec!["play".to_string()],
+                    allowance: Some(U128::from(1_000)),
+                }),
+            }]),
+        );
+
+        let (status, _, pending_owner) = contract.get_status();
+        assert_eq!(status, DeedStatus::Pending);
+        assert_eq!(
+            pending_owner,
+            Some(Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap())
+        );
+    }
+
+    fn lease_key() -> AccessKey {
+        AccessKey {
+            public_key: signer_pk(),
+            permission: Some(FunctionCallPermission {
+                receiver_id: accounts(3).to_string(),
+                method_names: vec![],
+                allowance: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_lease() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.add_lease_key(lease_key());
+        assert_eq!(contract.get_lease_key(), Some(signer_pk()));
+
+        contract.remove_lease_key();
+        assert_eq!(contract.get_lease_key(), Some(signer_pk()), "Only cleared once removal is confirmed");
+
+        // renter already deleted the key, still counts as removed
+        testing_env_with_promise_results(context2.build(), PromiseResult::Failed);
+        contract.remove_lease_callback();
+        assert_eq!(contract.get_lease_key(), None);
+        assert_eq!(contract.get_status().0, DeedStatus::Escrowed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lease key must be a function call key")]
+    fn test_lease_full_access() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.add_lease_key(AccessKey { public_key: signer_pk(), permission: None });
+    }
+
+    #[test]
+    #[should_panic(expected = "Deed is leased")]
+    fn test_transfer_ownership_leased() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.add_lease_key(lease_key());
+        contract.change_ownership(Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_transfer_ownership_rollback() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.change_ownership(Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap(), None);
+        assert_eq!(contract.underwriter, AccountId::default());
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.ownership_callback();
+
+        let (status, underwriter, pending_owner) = contract.get_status();
+        assert_eq!(status, DeedStatus::Failed);
+        assert_eq!(underwriter, accounts(1).to_string(), "Original underwriter must be restored");
+        assert!(pending_owner.is_none());
+    }
+
+    #[test]
+    fn test_reservation() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.reserve(accounts(2));
+        assert_eq!(contract.get_status().0, DeedStatus::Reserved);
+
+        // another deed in the lot failed
+        contract.settle_reservation(false);
+        let (status, underwriter, _) = contract.get_status();
+        assert_eq!(status, DeedStatus::Escrowed);
+        assert_eq!(underwriter, accounts(1).to_string(), "Original underwriter must be restored");
+
+        contract.reserve(accounts(2));
+        contract.settle_reservation(true);
+        assert_eq!(contract.get_status().0, DeedStatus::Claimable);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deed is not claimable")]
+    fn test_claim_reserved() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.reserve(accounts(2));
+
+        let context3 = get_context(accounts(1), accounts(1), accounts(2));
+        testing_env!(context3.build());
+        contract.claim(signer_pk(), None);
+    }
+
+    #[test]
+    fn test_claim() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.set_claimant(accounts(2));
+        assert_eq!(contract.get_status().0, DeedStatus::Claimable);
+
+        let context3 = get_context(accounts(1), accounts(2), accounts(2));
+        testing_env!(context3.build());
+        contract.claim(Base58PublicKey::try_from("ed25519:2mXmCTrFHMYTBv2kUEKGrKwk1wdT5EfXmFL85P6Xr9dV".to_string()).unwrap(), None);
+        assert_eq!(contract.get_status().0, DeedStatus::Pending);
+
+        testing_env_with_promise_results(context3.build(), PromiseResult::Failed);
+        contract.ownership_callback();
+        assert_eq!(contract.get_status().0, DeedStatus::Claimable, "Claimant can retry");
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized access, claimant only")]
+    fn test_claim_unauthorized() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        let context2 = get_context(accounts(1), accounts(1), accounts(0));
+        testing_env!(context2.build());
+        contract.set_claimant(accounts(2));
+
+        let context3 = get_context(accounts(1), accounts(3), accounts(3));
+        testing_env!(context3.build());
+        contract.claim(signer_pk(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Ownership has not been transferred")]
+    fn test_cleanup_before_transfer() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let contract = create_blank_account_manager();
+
+        contract.cleanup(None);
+    }
+
+    #[test]
+    fn test_cleanup() {
+        let context = get_context(accounts(1), accounts(1), accounts(1));
+        testing_env!(context.build());
+        let mut contract = create_blank_account_manager();
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(vec![]));
+        contract.ownership_callback();
+        assert_eq!(contract.status, DeedStatus::Transferred);
+
+        env::state_write(&contract);
+        contract.cleanup(None);
+        assert!(!env::state_exists(), "Deed state must be removed");
     }
 }
\ No newline at end of file
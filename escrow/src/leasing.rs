@@ -0,0 +1,207 @@
+use crate::*;
+
+/// Terms an underwriter offers for renting out an escrowed title
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeaseListing {
+    pub underwriter: AccountId,
+    pub price: U128,
+    pub duration_blocks: BlockHeight,
+    /// Renter key is always a function call key, a full access key could take the account over
+    pub permission: FunctionCallPermission,
+}
+
+/// Active lease, escrow holds the lock until the renter key is removed again
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Lease {
+    pub underwriter: AccountId,
+    pub renter: AccountId,
+    pub public_key: Base58PublicKey,
+    pub price: U128,
+    pub expires_block: BlockHeight,
+    /// True once the deed confirmed the renter key
+    pub is_active: bool,
+}
+
+#[near_bindgen]
+impl Escrow {
+    /// List an escrowed title for rent, for a fixed price per lease of duration_blocks.
+    /// Permission restricts the renter key to a single contract, which cannot be the title itself.
+    /// Listing again replaces the previous terms.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ list_lease '{"title": "some_account.testnet", "price": "1000000000000000000000000", "duration_blocks": 100000, "permission": {"receiver_id": "app.testnet", "method_names": [], "allowance": null}}' --accountId underwriter.testnet
+    /// ```
+    pub fn list_lease(
+        &mut self,
+        title: ValidAccountId,
+        price: U128,
+        duration_blocks: BlockHeight,
+        permission: FunctionCallPermission,
+    ) {
+        let acct_id = title.to_string();
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+        assert!(duration_blocks > 0, "Lease duration must be greater than zero");
+        assert_ne!(permission.receiver_id, acct_id, "Lease key cannot call the title account");
+
+        self.lease_listings.insert(&acct_id, &LeaseListing {
+            underwriter,
+            price,
+            duration_blocks,
+            permission,
+        });
+        log!("Lease listed: {} {}", &acct_id, price.0);
+    }
+
+    /// Remove a lease listing, does not affect an active lease
+    ///
+    /// ```bash
+    /// near call _escrow_account_ cancel_lease_listing '{"title": "some_account.testnet"}' --accountId underwriter.testnet
+    /// ```
+    pub fn cancel_lease_listing(&mut self, title: ValidAccountId) {
+        let acct_id = title.to_string();
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+
+        self.lease_listings.remove(&acct_id);
+    }
+
+    /// Rent a listed title, attached deposit must cover the price.
+    /// Escrow locks the title & the deed adds public_key for the renter.
+    /// Underwriter is paid once the deed confirms the key, the renter is refunded otherwise.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ rent '{"title": "some_account.testnet", "public_key": "ed25591:PK_HERE"}' --accountId renter.testnet --amount 1 --gas 200000000000000
+    /// ```
+    #[payable]
+    pub fn rent(&mut self, title: ValidAccountId, public_key: Base58PublicKey) -> Promise {
+        let acct_id = title.to_string();
+        let listing = self.lease_listings.get(&acct_id).expect("No lease listing found");
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(listing.underwriter, underwriter, "Lease listing is no longer valid");
+        assert!(!self.locks.contains_key(&acct_id), "Title is locked");
+        let deposit = env::attached_deposit();
+        assert!(deposit >= listing.price.0, "Deposit must cover lease price");
+
+        // Escrow holds the lock, so nobody can settle or move the title during the lease
+        self.locks.insert(&acct_id, &env::current_account_id());
+        self.leases.insert(&acct_id, &Lease {
+            underwriter,
+            renter: env::predecessor_account_id(),
+            public_key: public_key.clone(),
+            price: listing.price,
+            expires_block: env::block_index() + listing.duration_blocks,
+            is_active: false,
+        });
+
+        // Return anything extra
+        if deposit > listing.price.0 {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - listing.price.0);
+        }
+
+        ext_deed::add_lease_key(
+            AccessKey {
+                public_key,
+                permission: Some(listing.permission),
+            },
+            &acct_id,
+            0,
+            LEASE_GAS_FEE,
+        ).then(
+            ext_self::lease_callback(
+                acct_id,
+                &env::current_account_id(),
+                0,
+                LEASE_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Lease Callback
+    /// Pays the underwriter if the deed added the renter key, otherwise refunds the renter & unlocks
+    #[private]
+    pub fn lease_callback(&mut self, title: AccountId) -> bool {
+        let added = match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice::<bool>(&result).unwrap_or(true),
+            PromiseResult::Failed => false,
+            PromiseResult::NotReady => unreachable!(),
+        };
+        let mut lease = self.leases.get(&title).expect("No lease found");
+
+        if added {
+            lease.is_active = true;
+            self.leases.insert(&title, &lease);
+            Promise::new(lease.underwriter).transfer(lease.price.0);
+            log!("Lease started: {} until {}", &title, lease.expires_block);
+        } else {
+            self.leases.remove(&title);
+            self.locks.remove(&title);
+            Promise::new(lease.renter).transfer(lease.price.0);
+            log!("Lease failed, renter refunded: {}", &title);
+        }
+        added
+    }
+
+    /// End an expired lease, callable by anyone (or croncat).
+    /// The deed removes the renter key, returning sole control to escrow.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ end_lease '{"title": "some_account.testnet"}' --accountId anyone.testnet --gas 200000000000000
+    /// ```
+    pub fn end_lease(&mut self, title: ValidAccountId) -> Promise {
+        let acct_id = title.to_string();
+        let lease = self.leases.get(&acct_id).expect("No lease found");
+        assert!(lease.is_active, "Lease is not active");
+        assert!(env::block_index() > lease.expires_block, "Lease has not expired");
+
+        ext_deed::remove_lease_key(
+            &acct_id,
+            0,
+            LEASE_GAS_FEE,
+        ).then(
+            ext_self::end_lease_callback(
+                acct_id,
+                &env::current_account_id(),
+                0,
+                LEASE_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// End Lease Callback
+    /// Releases the lock once the renter key is gone, otherwise the lease stays so it can be retried
+    #[private]
+    pub fn end_lease_callback(&mut self, title: AccountId) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                self.leases.remove(&title);
+                self.locks.remove(&title);
+                log!("Lease ended: {}", &title);
+            }
+            PromiseResult::Failed => {
+                log!("Lease key removal failed: {}", &title);
+            }
+            PromiseResult::NotReady => unreachable!(),
+        };
+    }
+
+    /// Get the lease listing for a title
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_lease_listing '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_lease_listing(&self, title: ValidAccountId) -> Option<LeaseListing> {
+        self.lease_listings.get(&title.to_string())
+    }
+
+    /// Get the active lease for a title, if any
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_lease '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_lease(&self, title: ValidAccountId) -> Option<Lease> {
+        self.leases.get(&title.to_string())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::*;
+
+/// Standing loan offer on a title, principal is held by escrow until accepted or canceled
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LoanOffer {
+    pub lender: AccountId,
+    pub principal: U128,
+    pub repayment: U128,
+    pub duration_blocks: BlockHeight,
+}
+
+/// Active loan, the title stays locked by escrow until repaid or defaulted
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Loan {
+    pub lender: AccountId,
+    pub borrower: AccountId,
+    pub principal: U128,
+    pub repayment: U128,
+    pub due_block: BlockHeight,
+}
+
+#[near_bindgen]
+impl Escrow {
+    /// Offer Loan:
+    /// Offer NEAR (the attached deposit) against an escrowed title, to be repaid within duration_blocks.
+    /// Offering again on the same title replaces the previous offer, refunding it.
+    /// Principal must be at least 0.1 NEAR. Once a title has 10 offers, a new offer must beat
+    /// the smallest one, which is refunded.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ offer_loan '{"title": "some_account.testnet", "repayment": "11000000000000000000000000", "duration_blocks": 1000000}' --accountId lender.testnet --amount 10
+    /// ```
+    #[payable]
+    pub fn offer_loan(&mut self, title: ValidAccountId, repayment: U128, duration_blocks: BlockHeight) {
+        let acct_id = title.to_string();
+        let principal = env::attached_deposit();
+        assert!(self.accounts.contains_key(&acct_id), "Account is not in escrow");
+        assert!(principal >= MIN_LOAN_PRINCIPAL, "Loan amount is below minimum");
+        assert!(repayment.0 >= principal, "Repayment must cover principal");
+        assert!(duration_blocks > 0, "Loan duration must be greater than zero");
+
+        let lender = env::predecessor_account_id();
+        let mut offers = self.loan_offers.get(&acct_id).unwrap_or_default();
+
+        // Replace any previous offer from this account
+        if let Some(index) = offers.iter().position(|o| o.lender == lender) {
+            let previous = offers.remove(index);
+            Promise::new(lender.clone()).transfer(previous.principal.0);
+        }
+
+        // Keep offers bounded, so accepting can refund all of them
+        if offers.len() >= MAX_LOAN_OFFERS {
+            let (index, smallest) = offers.iter()
+                .enumerate()
+                .min_by_key(|(_, o)| o.principal.0)
+                .map(|(i, o)| (i, o.principal.0))
+                .unwrap();
+            assert!(principal > smallest, "Loan offers are full, must beat the smallest offer");
+            let evicted = offers.remove(index);
+            Promise::new(evicted.lender).transfer(evicted.principal.0);
+        }
+
+        offers.push(LoanOffer {
+            lender,
+            principal: U128(principal),
+            repayment,
+            duration_blocks,
+        });
+        self.loan_offers.insert(&acct_id, &offers);
+        log!("Loan offer: {} {}", &acct_id, principal);
+    }
+
+    /// Cancel Loan Offer:
+    /// Refund a loan offer that has not been accepted
+    ///
+    /// ```bash
+    /// near call _escrow_account_ cancel_loan_offer '{"title": "some_account.testnet"}' --accountId lender.testnet
+    /// ```
+    pub fn cancel_loan_offer(&mut self, title: ValidAccountId) {
+        let acct_id = title.to_string();
+        let lender = env::predecessor_account_id();
+        let mut offers = self.loan_offers.get(&acct_id).expect("No loan offers found");
+        let index = offers.iter().position(|o| o.lender == lender).expect("No loan offer found");
+
+        let offer = offers.remove(index);
+        if offers.is_empty() {
+            self.loan_offers.remove(&acct_id);
+        } else {
+            self.loan_offers.insert(&acct_id, &offers);
+        }
+
+        Promise::new(lender).transfer(offer.principal.0);
+    }
+
+    /// Accept Loan:
+    /// Underwriter accepts a lender's offer, escrow locks the title for the loan term & pays out the principal.
+    /// The expected terms must match the offer, so a lender cannot swap in worse terms before it is accepted.
+    /// All other loan offers on the title are refunded.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ accept_loan '{"title": "some_account.testnet", "lender": "lender.testnet", "principal": "10000000000000000000000000", "repayment": "11000000000000000000000000", "duration_blocks": 1000000}' --accountId underwriter.testnet
+    /// ```
+    pub fn accept_loan(
+        &mut self,
+        title: ValidAccountId,
+        lender: ValidAccountId,
+        principal: U128,
+        repayment: U128,
+        duration_blocks: BlockHeight,
+    ) {
+        let acct_id = title.to_string();
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+        assert!(!self.locks.contains_key(&acct_id), "Title is locked");
+
+        let offers = self.loan_offers.remove(&acct_id).expect("No loan offers found");
+        let offer = offers.iter().find(|o| o.lender == lender.to_string()).cloned().expect("No loan offer found");
+        assert!(
+            offer.principal == principal && offer.repayment == repayment && offer.duration_blocks == duration_blocks,
+            "Loan offer terms changed"
+        );
+
+        // Refund everyone else
+        for other in offers.into_iter().filter(|o| o.lender != offer.lender) {
+            Promise::new(other.lender).transfer(other.principal.0);
+        }
+
+        // Escrow holds the lock, so nobody can settle or move the title during the term
+        self.locks.insert(&acct_id, &env::current_account_id());
+        self.sales.remove(&acct_id);
+        self.loans.insert(&acct_id, &Loan {
+            lender: offer.lender,
+            borrower: underwriter.clone(),
+            principal: offer.principal,
+            repayment: offer.repayment,
+            due_block: env::block_index() + offer.duration_blocks,
+        });
+
+        Promise::new(underwriter).transfer(offer.principal.0);
+        log!("Loan accepted: {}", &acct_id);
+    }
+
+    /// Repay Loan:
+    /// Attached deposit must cover the repayment, which goes to the lender & releases the lock.
+    /// Anyone can repay on behalf of the borrower, any excess is refunded.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ repay_loan '{"title": "some_account.testnet"}' --accountId underwriter.testnet --amount 11
+    /// ```
+    #[payable]
+    pub fn repay_loan(&mut self, title: ValidAccountId) {
+        let acct_id = title.to_string();
+        let loan = self.loans.get(&acct_id).expect("No loan found");
+        let deposit = env::attached_deposit();
+        assert!(deposit >= loan.repayment.0, "Deposit must cover repayment");
+
+        self.loans.remove(&acct_id);
+        self.locks.remove(&acct_id);
+
+        // Return anything extra
+        if deposit > loan.repayment.0 {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - loan.repayment.0);
+        }
+        Promise::new(loan.lender).transfer(loan.repayment.0);
+        log!("Loan repaid: {}", &acct_id);
+    }
+
+    /// Claim Default:
+    /// Once the loan is past due, the lender takes over the underwriting rights.
+    /// The title stays in escrow, the lender can then sell or auction it through a registrar.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ claim_default '{"title": "some_account.testnet"}' --accountId lender.testnet
+    /// ```
+    pub fn claim_default(&mut self, title: ValidAccountId) {
+        let acct_id = title.to_string();
+        let loan = self.loans.get(&acct_id).expect("No loan found");
+        assert_eq!(loan.lender, env::predecessor_account_id(), "Unauthorized access, lender only");
+        assert!(env::block_index() > loan.due_block, "Loan is not past due");
+
+        self.loans.remove(&acct_id);
+        self.locks.remove(&acct_id);
+        self.internal_set_underwriter(&acct_id, &loan.lender);
+        log!("Loan defaulted: {} to {}", &acct_id, &loan.lender);
+    }
+
+    /// Get the active loan for a title, if any
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_loan '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_loan(&self, title: ValidAccountId) -> Option<Loan> {
+        self.loans.get(&title.to_string())
+    }
+
+    /// Get the open loan offers for a title
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_loan_offers '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_loan_offers(&self, title: ValidAccountId) -> Vec<LoanOffer> {
+        self.loan_offers.get(&title.to_string()).unwrap_or_default()
+    }
+}
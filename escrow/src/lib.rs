@@ -2,34 +2,160 @@ use near_sdk::{
     ext_contract,
     near_bindgen,
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::{ LookupMap },
-    json_types::{ ValidAccountId, Base58PublicKey },
+    serde::{Deserialize, Serialize},
+    collections::{ LookupMap, UnorderedSet },
+    json_types::{ ValidAccountId, Base58PublicKey, Base64VecU8, U128 },
     AccountId,
+    BlockHeight,
     env,
     log,
     Promise,
+    PromiseResult,
     BorshStorageKey,
     PanicOnDefault,
+    assert_one_yocto,
     StorageUsage,
 };
 
+use std::collections::{BTreeMap, HashMap};
+
+mod leasing;
+mod lending;
+mod nft;
+pub use crate::leasing::*;
+pub use crate::lending::*;
+pub use crate::nft::*;
+
 near_sdk::setup_alloc!();
 
 // TODO: Finalize amounts needed!
 // Ⓝa Ⓝa Ⓝa Ⓝa Ⓝa Ⓝa Ⓝa Ⓝa - Batmannnnnnnn
 pub const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
-const CLOSE_ESCROW_GAS_FEE: u64 = 50_000_000_000_000; // 50 Tgas
+const CLOSE_ESCROW_GAS_FEE: u64 = 30_000_000_000_000; // 30 Tgas
+const CHECK_AUCTION_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const CHECK_SWEEP_GAS_FEE: u64 = 5_000_000_000_000; // 5 Tgas
+const REGISTER_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const WITHDRAW_CALLBACK_GAS_FEE: u64 = 100_000_000_000_000; // 100 Tgas
+const SETTLE_CALLBACK_GAS_FEE: u64 = 20_000_000_000_000; // 20 Tgas
+const LOT_RESERVE_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const SETTLE_LOT_CALLBACK_GAS_FEE: u64 = 100_000_000_000_000; // 100 Tgas
+const LEASE_GAS_FEE: u64 = 50_000_000_000_000; // 50 Tgas
+const LEASE_CALLBACK_GAS_FEE: u64 = 20_000_000_000_000; // 20 Tgas
+const DEFAULT_MAX_ROYALTY_BPS: u32 = 1_000; // 10%
+const MAX_ROYALTY_RECIPIENTS: usize = 10;
+const MIN_LOAN_PRINCIPAL: u128 = ONE_NEAR / 10;
+const MAX_LOAN_OFFERS: usize = 10;
+const BPS_DENOMINATOR: u128 = 10_000;
 
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKeys {
     Accounts,
     Tlas,
+    Sales,
+    Locks,
+    PendingUnderwriters,
+    Titles,
+    TitlesByUnderwriter,
+    UnderwriterTitles { account_hash: Vec<u8> },
+    Registrars,
+    Royalties,
+    Approvals,
+    Loans,
+    LoanOffers,
+    Leases,
+    LeaseListings,
+}
+
+/// Fixed price listing of an escrowed title, optionally for a single designated buyer
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sale {
+    pub underwriter: AccountId,
+    pub price: U128,
+    pub buyer: Option<AccountId>,
+}
+
+/// Mirrors deed FunctionCallPermission
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FunctionCallPermission {
+    pub receiver_id: AccountId,
+    pub method_names: Vec<String>,
+    pub allowance: Option<U128>,
+}
+
+/// Mirrors deed AccessKey, permission of None is a full access key
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccessKey {
+    pub public_key: Base58PublicKey,
+    pub permission: Option<FunctionCallPermission>,
+}
+
+/// Funds moving together with a title, only paid out once the deed confirms the transfer
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub amount: U128,
+    pub recipient: AccountId,
+    /// Refunded here if the transfer fails, defaults to the caller
+    pub refund_to: Option<AccountId>,
+}
+
+/// Royalty owed to recipients (in basis points) on every sale of a title.
+/// Only the first underwriter to register a title can set it, and only once.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Royalty {
+    pub creator: AccountId,
+    pub recipients: HashMap<AccountId, u32>,
+    pub is_set: bool,
 }
 
 #[ext_contract(ext_deed)]
 pub trait ExtDeed {
     fn new(underwriter: ValidAccountId, escrow: ValidAccountId, registrar: Option<ValidAccountId>) -> Self;
-    fn change_ownership(&mut self, pk: Base58PublicKey) -> Promise;
+    fn change_ownership(&mut self, pk: Base58PublicKey, extra_keys: Option<Vec<AccessKey>>) -> Promise;
+    fn set_claimant(&mut self, claimant: ValidAccountId);
+    fn reserve(&mut self, claimant: ValidAccountId);
+    fn settle_reservation(&mut self, confirmed: bool);
+    fn add_lease_key(&mut self, key: AccessKey) -> Promise;
+    fn remove_lease_key(&mut self) -> Promise;
+    fn is_swept(&self) -> bool;
+}
+
+#[ext_contract(ext_registrar)]
+pub trait ExtRegistrar {
+    fn is_auction_active(&self, id: AccountId) -> bool;
+}
+
+#[ext_contract(ext_self)]
+pub trait ExtSelf {
+    fn register_callback(&mut self, title: AccountId, underwriter: AccountId, deposit: U128) -> bool;
+    fn withdraw_callback(
+        &mut self,
+        title: AccountId,
+        underwriter: AccountId,
+        locker: AccountId,
+        new_key: Base58PublicKey,
+        extra_keys: Option<Vec<AccessKey>>,
+    ) -> Promise;
+    fn settle_callback(&mut self, title: AccountId, underwriter: AccountId, payout: Option<Payout>) -> bool;
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: AccountId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool;
+    fn settle_lot_callback(
+        &mut self,
+        titles: Vec<AccountId>,
+        underwriters: Vec<AccountId>,
+        payout: Option<Payout>,
+    ) -> bool;
+    fn lease_callback(&mut self, title: AccountId) -> bool;
+    fn end_lease_callback(&mut self, title: AccountId);
 }
 
 #[near_bindgen]
@@ -43,13 +169,47 @@ pub struct Escrow {
     /// mainnet TLA factory: "registrar"
     pub factory: AccountId,
 
-    /// The account that handles all logic for auctions, bids & other actions
-    pub registrar: AccountId,
+    /// The accounts that handle all logic for auctions, bids & other actions
+    /// e.g. an auction house, an OTC desk, a lending contract. Managed by DAO.
+    registrars: UnorderedSet<AccountId>,
 
     // keeps track of the escrowed accounts
     tlas: LookupMap<AccountId, AccountId>,
     accounts: LookupMap<AccountId, AccountId>,
 
+    // direct sales, title -> listing
+    sales: LookupMap<AccountId, Sale>,
+
+    // titles that can only be settled by the locking account, title -> locker
+    locks: LookupMap<AccountId, AccountId>,
+
+    // underwriter reassignments waiting on acceptance, title -> new underwriter
+    pending_underwriters: LookupMap<AccountId, AccountId>,
+
+    // iterable index of escrowed titles, overall & per underwriter
+    titles: UnorderedSet<AccountId>,
+    titles_by_underwriter: LookupMap<AccountId, UnorderedSet<AccountId>>,
+
+    // stats
+    total_registered: u64,
+    total_closed: u64,
+
+    // royalties survive the title leaving escrow, title -> royalty
+    royalties: LookupMap<AccountId, Royalty>,
+    pub max_royalty_bps: u32,
+
+    // NEP-178 approvals, cleared whenever the underwriter changes, title -> approved account -> approval id
+    approvals: LookupMap<AccountId, HashMap<AccountId, u64>>,
+    next_approval_id: u64,
+
+    // name-collateralized lending, title -> active loan & title -> open offers
+    loans: LookupMap<AccountId, Loan>,
+    loan_offers: LookupMap<AccountId, Vec<LoanOffer>>,
+
+    // rentals, title -> active lease & title -> listed terms
+    leases: LookupMap<AccountId, Lease>,
+    lease_listings: LookupMap<AccountId, LeaseListing>,
+
     // Optional
     pub dao: Option<AccountId>,
 
@@ -77,12 +237,30 @@ impl Escrow {
         assert!(!env::state_exists(), "The contract is already initialized");
         assert_eq!(env::current_account_id(), env::predecessor_account_id(), "Must be called by owner");
 
+        let mut registrars = UnorderedSet::new(StorageKeys::Registrars);
+        registrars.insert(&registrar.to_string());
+
         let mut this = Escrow {
             base_storage_usage: 0,
             factory: factory.to_string(),
-            registrar: registrar.to_string(),
+            registrars,
             tlas: LookupMap::new(StorageKeys::Tlas),
             accounts: LookupMap::new(StorageKeys::Accounts),
+            sales: LookupMap::new(StorageKeys::Sales),
+            locks: LookupMap::new(StorageKeys::Locks),
+            pending_underwriters: LookupMap::new(StorageKeys::PendingUnderwriters),
+            titles: UnorderedSet::new(StorageKeys::Titles),
+            titles_by_underwriter: LookupMap::new(StorageKeys::TitlesByUnderwriter),
+            total_registered: 0,
+            total_closed: 0,
+            royalties: LookupMap::new(StorageKeys::Royalties),
+            max_royalty_bps: DEFAULT_MAX_ROYALTY_BPS,
+            approvals: LookupMap::new(StorageKeys::Approvals),
+            next_approval_id: 0,
+            loans: LookupMap::new(StorageKeys::Loans),
+            loan_offers: LookupMap::new(StorageKeys::LoanOffers),
+            leases: LookupMap::new(StorageKeys::Leases),
+            lease_listings: LookupMap::new(StorageKeys::LeaseListings),
             dao,
         };
         // compute storage needs before finishing
@@ -105,50 +283,679 @@ impl Escrow {
     /// escrow is the sole owner, and can only transfer ownership upon
     /// close of title
     ///
+    /// The caller must be a deed whose key sweep succeeded (`is_swept`), checked before registering.
+    /// The sweep only deletes the keys listed at deploy, a contract cannot list its own keys,
+    /// so anyone relying on a title should still check `near keys` on it comes back empty.
+    /// A caller that is not a swept deed is refunded its deposit & not registered.
+    ///
     /// ```bash
     /// near call _escrow_account_ register '{"underwriter": "some_other_account.testnet"}' --accountId youraccount_to_auction.testnet
     /// ```
     ///
     #[payable]
-    pub fn register(&mut self, underwriter: AccountId) {
+    pub fn register(&mut self, underwriter: AccountId) -> Promise {
         let acct = env::predecessor_account_id();
         // Make sure this account isnt already in escrow
-        assert_ne!(self.accounts.contains_key(&acct), true, "Account already in escrow");
+        assert!(!self.accounts.contains_key(&acct), "Account already in escrow");
+
+        // Confirm the deed swept its keys, before taking custody
+        ext_deed::is_swept(
+            &acct,
+            0,
+            CHECK_SWEEP_GAS_FEE,
+        ).then(
+            ext_self::register_callback(
+                acct.clone(),
+                underwriter,
+                U128::from(env::attached_deposit()),
+                &env::current_account_id(),
+                0,
+                REGISTER_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Register Callback
+    /// Stores the title once its deed confirmed the sweep, a failed check counts as not swept
+    #[private]
+    pub fn register_callback(&mut self, title: AccountId, underwriter: AccountId, deposit: U128) -> bool {
+        let is_swept = match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice(&result).unwrap_or(false),
+            _ => false,
+        };
+        if !is_swept || self.accounts.contains_key(&title) {
+            log!("Deed is not swept or already in escrow: {}", &title);
+            if deposit.0 > 0 {
+                Promise::new(title).transfer(deposit.0);
+            }
+            return false;
+        }
 
         // Store the account in escrow
-        self.accounts.insert(&acct, &underwriter);
-        log!("Account {} is in escrow", &acct);
+        self.accounts.insert(&title, &underwriter);
+        self.internal_index_add(&title, &underwriter);
+        self.total_registered += 1;
+
+        // First underwriter ever is the only one allowed to set royalties
+        if !self.royalties.contains_key(&title) {
+            self.royalties.insert(&title, &Royalty {
+                creator: underwriter.clone(),
+                recipients: HashMap::new(),
+                is_set: false,
+            });
+        }
+        log!("Account {} is in escrow", &title);
+        true
+    }
+
+    /// Lock a title while a registrar has an active auction (or other settlement) for it.
+    /// Locked titles can only be closed by the locking registrar, until it unlocks or settles them.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ lock '{"title": "some_account.testnet"}' --accountId registrar.testnet
+    /// ```
+    pub fn lock(&mut self, title: ValidAccountId) {
+        assert!(self.registrars.contains(&env::predecessor_account_id()), "Unauthorized access, registrar only");
+        assert!(self.accounts.contains_key(&title.to_string()), "Account is not in escrow");
+        assert!(!self.locks.contains_key(&title.to_string()), "Title is locked");
+
+        self.locks.insert(&title.to_string(), &env::predecessor_account_id());
+        log!("Lock: {}", &title);
+    }
+
+    /// Lock many titles at once, for a registrar batch. Titles not in escrow or already locked are skipped.
+    /// Returns whether each title was locked, in order.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ lock_many '{"titles": ["acme.testnet", "acme-app.testnet"]}' --accountId registrar.testnet
+    /// ```
+    pub fn lock_many(&mut self, titles: Vec<ValidAccountId>) -> Vec<bool> {
+        assert!(self.registrars.contains(&env::predecessor_account_id()), "Unauthorized access, registrar only");
+
+        titles.iter()
+            .map(|title| {
+                let acct_id = title.to_string();
+                if !self.accounts.contains_key(&acct_id) || self.locks.contains_key(&acct_id) {
+                    return false;
+                }
+                self.locks.insert(&acct_id, &env::predecessor_account_id());
+                log!("Lock: {}", &acct_id);
+                true
+            })
+            .collect()
+    }
+
+    /// Release a lock without settling the title, only callable by the locking account
+    ///
+    /// ```bash
+    /// near call _escrow_account_ unlock '{"title": "some_account.testnet"}' --accountId registrar.testnet
+    /// ```
+    pub fn unlock(&mut self, title: ValidAccountId) {
+        let locker = self.locks.get(&title.to_string()).expect("Title is not locked");
+        assert_eq!(locker, env::predecessor_account_id(), "Unauthorized access, lock holder only");
+
+        self.locks.remove(&title.to_string());
+        log!("Unlock: {}", &title);
     }
 
     // TODO: support TLAs
     /// The full realization of an escrow deed, where the account is
     /// transferred to the new owner OR the old owner.
     /// If the account was registered with registrar, then we must check the signer.
+    /// Locked titles can only be closed by the registrar holding the lock, regardless of signer.
+    /// Accounts approved via nft_approve (e.g. a marketplace) can also close the title.
+    /// Optional extra_keys (full access or function call) are added by the deed in the same batch.
+    /// Optional payout must be attached as deposit, it is only paid once the deed confirms the new owner.
+    /// If the deed fails, the payout is refunded and the title returns to escrow.
+    ///
+    /// near call _escrow_account_ close_escrow '{"title": "some_account.testnet", "new_key": "ed25591:PK_HERE", "extra_keys": [{"public_key": "ed25591:PK_HERE", "permission": {"receiver_id": "app.testnet", "method_names": [], "allowance": null}}]}' --accountId youraccount.testnet
+    /// ```
+    #[payable]
+    pub fn close_escrow(
+        &mut self,
+        title: ValidAccountId,
+        new_key: Base58PublicKey,
+        extra_keys: Option<Vec<AccessKey>>,
+        payout: Option<Payout>,
+    ) -> Promise {
+        let payout = self.assert_payout(payout);
+        let (acct_id, underwriter) = self.internal_close(&title);
+
+        // Call the deed, to transfer ownership to new public key(s)
+        let transfer = ext_deed::change_ownership(
+            new_key,
+            extra_keys,
+            &acct_id,
+            0,
+            CLOSE_ESCROW_GAS_FEE,
+        );
+        self.internal_settle(acct_id, underwriter, transfer, payout)
+    }
+
+    /// Same as close_escrow, but the new owner is identified by account instead of a public key.
+    /// The deed marks the claimant, who then calls "claim" on the deed with a key of their choice.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ close_escrow_to_account '{"title": "some_account.testnet", "claimant": "winner.testnet"}' --accountId youraccount.testnet
+    /// ```
+    #[payable]
+    pub fn close_escrow_to_account(
+        &mut self,
+        title: ValidAccountId,
+        claimant: ValidAccountId,
+        payout: Option<Payout>,
+    ) -> Promise {
+        let payout = self.assert_payout(payout);
+        let (acct_id, underwriter) = self.internal_close(&title);
+
+        // Call the deed, to authorize the claimant
+        let transfer = ext_deed::set_claimant(
+            claimant,
+            &acct_id,
+            0,
+            CLOSE_ESCROW_GAS_FEE,
+        );
+        self.internal_settle(acct_id, underwriter, transfer, payout)
+    }
+
+    /// Settle Callback
+    /// Releases the payout if the deed transferred, otherwise refunds it and restores the title
+    #[private]
+    pub fn settle_callback(&mut self, title: AccountId, underwriter: AccountId, payout: Option<Payout>) -> bool {
+        let transferred = match env::promise_result(0) {
+            // set_claimant returns nothing, change_ownership returns whether the key was added
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice::<bool>(&result).unwrap_or(true),
+            PromiseResult::Failed => false,
+            PromiseResult::NotReady => unreachable!(),
+        };
+
+        if transferred {
+            if let Some(Payout { amount, recipient, refund_to: _ }) = payout {
+                self.internal_pay(&title, amount.0, recipient);
+            }
+            log!("Settled: {}", &title);
+        } else {
+            if let Some(Payout { amount, recipient: _, refund_to }) = payout {
+                Promise::new(refund_to.unwrap_or_default()).transfer(amount.0);
+            }
+            self.internal_restore(&title, &underwriter);
+            log!("Settle failed, title returned to escrow: {}", &title);
+        }
+        transferred
+    }
+
+    /// Close several titles as one lot, all to the same winner account, who claims each deed with a key of their choice.
+    /// Every title is validated before any deed is called, if one cannot close none do.
+    /// Each deed first reserves itself for the winner, only once every deed reserved are they made claimable
+    /// and the payout released, split evenly per title so each title pays its own royalties.
+    /// If any deed fails, every reservation is released, the payout refunded and all titles restored.
     ///
-    /// near call _escrow_account_ close_escrow '{"title": "some_account.testnet", "new_key": "ed25591:PK_HERE"}' --accountId youraccount.testnet
+    /// ```bash
+    /// near call _escrow_account_ close_escrow_lot '{"titles": ["acme.testnet", "acme-app.testnet"], "winner": "winner.testnet"}' --accountId registrar.testnet --gas 300000000000000
     /// ```
-    pub fn close_escrow(&mut self, title: ValidAccountId, new_key: Base58PublicKey) -> Promise {
-        let acct_id = title.clone().to_string();
+    #[payable]
+    pub fn close_escrow_lot(
+        &mut self,
+        titles: Vec<ValidAccountId>,
+        winner: ValidAccountId,
+        payout: Option<Payout>,
+    ) -> Promise {
+        assert!(!titles.is_empty(), "Must include at least one title");
+        let payout = self.assert_payout(payout);
+        let closed: Vec<(AccountId, AccountId)> = titles.iter().map(|title| self.internal_close(title)).collect();
+
+        let reservations = closed.iter()
+            .map(|(acct_id, _)| ext_deed::reserve(winner.clone(), acct_id, 0, LOT_RESERVE_GAS_FEE))
+            .reduce(|a, b| a.and(b))
+            .unwrap();
+
+        let (titles, underwriters) = closed.into_iter().unzip();
+        reservations.then(
+            ext_self::settle_lot_callback(
+                titles,
+                underwriters,
+                payout,
+                &env::current_account_id(),
+                0,
+                SETTLE_LOT_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Settle Lot Callback
+    /// Confirms every reservation & releases the payout per title if all deeds reserved,
+    /// otherwise releases the reservations, refunds the payout & restores every title
+    #[private]
+    pub fn settle_lot_callback(
+        &mut self,
+        titles: Vec<AccountId>,
+        underwriters: Vec<AccountId>,
+        payout: Option<Payout>,
+    ) -> bool {
+        let results: Vec<bool> = (0..env::promise_results_count())
+            .map(|i| matches!(env::promise_result(i), PromiseResult::Successful(_)))
+            .collect();
+        let settled = results.len() == titles.len() && results.iter().all(|r| *r);
+
+        // Only deeds that reserved have anything to confirm or release
+        for (title, reserved) in titles.iter().zip(results.iter()) {
+            if *reserved {
+                ext_deed::settle_reservation(settled, title, 0, LOT_RESERVE_GAS_FEE);
+            }
+        }
+
+        if !settled {
+            if let Some(Payout { amount, recipient: _, refund_to }) = payout {
+                Promise::new(refund_to.unwrap_or_default()).transfer(amount.0);
+            }
+            for (title, underwriter) in titles.iter().zip(underwriters.iter()) {
+                self.internal_restore(title, underwriter);
+            }
+            log!("Lot settle failed, titles returned to escrow: {:?}", &titles);
+            return false;
+        }
+
+        if let Some(Payout { amount, recipient, refund_to: _ }) = payout {
+            // Even split per title, last title takes the remainder
+            let count = titles.len() as u128;
+            let share = amount.0 / count;
+            for (i, title) in titles.iter().enumerate() {
+                let title_amount = if i as u128 == count - 1 { amount.0 - share * (count - 1) } else { share };
+                self.internal_pay(title, title_amount, recipient.clone());
+            }
+        }
+        log!("Lot settled: {:?}", &titles);
+        true
+    }
+
+    /// Pays the proceeds of a title, royalties come out first & the recipient gets the rest
+    fn internal_pay(&self, title: &AccountId, amount: u128, recipient: AccountId) {
+        let mut remaining = amount;
+        if let Some(royalty) = self.royalties.get(title) {
+            for (account_id, bps) in royalty.recipients {
+                let share = amount * u128::from(bps) / BPS_DENOMINATOR;
+                if share > 0 {
+                    remaining -= share;
+                    Promise::new(account_id).transfer(share);
+                }
+            }
+        }
+        Promise::new(recipient).transfer(remaining);
+    }
+
+    /// Checks the caller controls the title, then removes it from escrow
+    fn internal_close(&mut self, title: &ValidAccountId) -> (AccountId, AccountId) {
+        let acct_id = title.to_string();
         let acct = self.accounts.get(&acct_id).expect("Account is not in escrow");
 
-        // Check that this is indeed the owner
-        if self.registrar == env::predecessor_account_id() {
+        // Check that this is indeed the owner, or the registrar settling a locked title
+        if let Some(locker) = self.locks.get(&acct_id) {
+            assert_eq!(locker, env::predecessor_account_id(), "Title is locked");
+        } else if self.registrars.contains(&env::predecessor_account_id()) {
             assert_eq!(acct, env::signer_account_id(), "Account does not control deed account");
-        } else {
+        } else if !self.internal_is_approved(&acct_id, &env::predecessor_account_id(), None) {
             assert_eq!(acct, env::predecessor_account_id(), "Account does not control deed account");
         }
 
-        // Remove from registry
-        self.accounts.remove(&acct_id);
-        log!("Close deed: {}", &acct_id);
+        self.internal_remove(&acct_id);
+        (acct_id, acct)
+    }
+
+    /// Checks the attached deposit covers the payout exactly, defaulting the refund to the caller
+    fn assert_payout(&self, payout: Option<Payout>) -> Option<Payout> {
+        let amount = payout.as_ref().map(|p| p.amount.0).unwrap_or(0);
+        assert_eq!(env::attached_deposit(), amount, "Deposit must match payout amount");
+
+        payout.map(|p| Payout {
+            refund_to: Some(p.refund_to.unwrap_or_else(env::predecessor_account_id)),
+            ..p
+        })
+    }
+
+    /// Chains the deed transfer with settlement of the payout
+    fn internal_settle(
+        &mut self,
+        title: AccountId,
+        underwriter: AccountId,
+        transfer: Promise,
+        payout: Option<Payout>,
+    ) -> Promise {
+        transfer.then(
+            ext_self::settle_callback(
+                title,
+                underwriter,
+                payout,
+                &env::current_account_id(),
+                0,
+                SETTLE_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Underwriter takes the title back out of escrow, transferring ownership to a key of their choice.
+    /// Only a registrar holding the lock can be auctioning the title, so an unlocked title is withdrawn right away.
+    /// A title locked by a registrar is only withdrawn once that registrar confirms no auction is active,
+    /// titles locked by escrow itself (loans, leases) cannot be withdrawn.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ withdraw_from_escrow '{"title": "some_account.testnet", "new_key": "ed25591:PK_HERE"}' --accountId underwriter.testnet --gas 200000000000000
+    /// ```
+    pub fn withdraw_from_escrow(
+        &mut self,
+        title: ValidAccountId,
+        new_key: Base58PublicKey,
+        extra_keys: Option<Vec<AccessKey>>,
+    ) -> Promise {
+        let acct_id = title.to_string();
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+
+        let locker = match self.locks.get(&acct_id) {
+            Some(locker) => locker,
+            None => return self.internal_withdraw(acct_id, new_key, extra_keys),
+        };
+        // Escrow locks loans & leases itself, a revoked registrar is still asked about titles it locked
+        assert_ne!(locker, env::current_account_id(), "Title is locked");
+
+        // Confirm the locking registrar has no auction for this title
+        ext_registrar::is_auction_active(
+            acct_id.clone(),
+            &locker,
+            0,
+            CHECK_AUCTION_GAS_FEE,
+        ).then(
+            ext_self::withdraw_callback(
+                acct_id,
+                underwriter,
+                locker,
+                new_key,
+                extra_keys,
+                &env::current_account_id(),
+                0,
+                WITHDRAW_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Withdraw Callback
+    /// The locking registrar failing to answer counts as an active auction, so withdrawal is refused.
+    /// Once it confirms no auction, its stale lock is released with the title.
+    #[private]
+    pub fn withdraw_callback(
+        &mut self,
+        title: AccountId,
+        underwriter: AccountId,
+        locker: AccountId,
+        new_key: Base58PublicKey,
+        extra_keys: Option<Vec<AccessKey>>,
+    ) -> Promise {
+        let is_active = match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice(&result).unwrap_or(true),
+            _ => true,
+        };
+        assert!(!is_active, "Auction is active for this title");
+        assert_eq!(self.locks.get(&title), Some(locker), "Title is locked");
+        // Make sure nothing changed while waiting on the registrar
+        assert_eq!(self.accounts.get(&title), Some(underwriter), "Account does not control deed account");
+
+        self.internal_withdraw(title, new_key, extra_keys)
+    }
+
+    /// Removes the title and hands it to the new key(s)
+    fn internal_withdraw(
+        &mut self,
+        title: AccountId,
+        new_key: Base58PublicKey,
+        extra_keys: Option<Vec<AccessKey>>,
+    ) -> Promise {
+        let underwriter = self.accounts.get(&title).expect("Account is not in escrow");
+        self.internal_remove(&title);
+
+        // Call the deed, to transfer ownership to new public key(s)
+        let transfer = ext_deed::change_ownership(
+            new_key,
+            extra_keys,
+            &title,
+            0,
+            CLOSE_ESCROW_GAS_FEE,
+        );
+        self.internal_settle(title, underwriter, transfer, None)
+    }
+
+    /// Transfer underwriting rights to another account, title stays in escrow.
+    /// With require_acceptance, the new underwriter must call "accept_underwriter" before it takes effect.
+    /// Not available while the title is locked.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ transfer_underwriter '{"title": "some_account.testnet", "new_underwriter": "other_wallet.testnet", "require_acceptance": true}' --accountId underwriter.testnet
+    /// ```
+    pub fn transfer_underwriter(
+        &mut self,
+        title: ValidAccountId,
+        new_underwriter: ValidAccountId,
+        require_acceptance: Option<bool>,
+    ) {
+        let acct_id = title.to_string();
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+        assert!(!self.locks.contains_key(&acct_id), "Title is locked");
+
+        if require_acceptance.unwrap_or(false) {
+            self.pending_underwriters.insert(&acct_id, &new_underwriter.to_string());
+            log!("Underwriter pending: {} {}", &acct_id, &new_underwriter);
+        } else {
+            self.internal_set_underwriter(&acct_id, &new_underwriter.to_string());
+        }
+    }
+
+    /// Accept a pending underwriter transfer
+    ///
+    /// ```bash
+    /// near call _escrow_account_ accept_underwriter '{"title": "some_account.testnet"}' --accountId other_wallet.testnet
+    /// ```
+    pub fn accept_underwriter(&mut self, title: ValidAccountId) {
+        let acct_id = title.to_string();
+        let pending = self.pending_underwriters.get(&acct_id).expect("No pending underwriter");
+        assert_eq!(pending, env::predecessor_account_id(), "Account is not the pending underwriter");
+        assert!(!self.locks.contains_key(&acct_id), "Title is locked");
+
+        self.internal_set_underwriter(&acct_id, &pending);
+    }
+
+    /// Get the pending underwriter for a title, if any
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_pending_underwriter '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_pending_underwriter(&self, title: ValidAccountId) -> Option<AccountId> {
+        self.pending_underwriters.get(&title.to_string())
+    }
+
+    /// Assigns a new underwriter, anything set up by the previous underwriter is cleared
+    fn internal_set_underwriter(&mut self, acct_id: &AccountId, underwriter: &AccountId) {
+        if let Some(previous) = self.accounts.insert(acct_id, underwriter) {
+            self.internal_index_remove(acct_id, &previous);
+        }
+        self.internal_index_add(acct_id, underwriter);
+        self.sales.remove(acct_id);
+        self.pending_underwriters.remove(acct_id);
+        self.approvals.remove(acct_id);
+        self.lease_listings.remove(acct_id);
+        log!("Underwriter: {} {}", acct_id, underwriter);
+    }
+
+    /// Removes a title from the registry, along with any open sale or lock
+    fn internal_remove(&mut self, acct_id: &AccountId) {
+        if let Some(underwriter) = self.accounts.remove(acct_id) {
+            self.internal_index_remove(acct_id, &underwriter);
+            self.total_closed += 1;
+        }
+        self.sales.remove(acct_id);
+        self.locks.remove(acct_id);
+        self.pending_underwriters.remove(acct_id);
+        self.approvals.remove(acct_id);
+        self.lease_listings.remove(acct_id);
+        log!("Close deed: {}", acct_id);
+    }
+
+    /// Returns a title to escrow after a failed transfer
+    fn internal_restore(&mut self, acct_id: &AccountId, underwriter: &AccountId) {
+        self.accounts.insert(acct_id, underwriter);
+        self.internal_index_add(acct_id, underwriter);
+        self.total_closed = self.total_closed.saturating_sub(1);
+    }
+
+    /// Adds a title to the iterable indexes
+    fn internal_index_add(&mut self, acct_id: &AccountId, underwriter: &AccountId) {
+        self.titles.insert(acct_id);
+        let mut titles = self.titles_by_underwriter.get(underwriter).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKeys::UnderwriterTitles {
+                account_hash: env::sha256(underwriter.as_bytes()),
+            })
+        });
+        titles.insert(acct_id);
+        self.titles_by_underwriter.insert(underwriter, &titles);
+    }
+
+    /// Removes a title from the iterable indexes
+    fn internal_index_remove(&mut self, acct_id: &AccountId, underwriter: &AccountId) {
+        self.titles.remove(acct_id);
+        if let Some(mut titles) = self.titles_by_underwriter.get(underwriter) {
+            titles.remove(acct_id);
+            if titles.is_empty() {
+                self.titles_by_underwriter.remove(underwriter);
+            } else {
+                self.titles_by_underwriter.insert(underwriter, &titles);
+            }
+        }
+    }
+
+    /// List an escrowed title for a fixed price, without an auction.
+    /// Only the underwriter can list, optionally restricting the sale to a single buyer.
+    /// Listing again replaces the previous price & buyer.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ list_sale '{"title": "some_account.testnet", "price": "10000000000000000000000000", "buyer": "buyer.testnet"}' --accountId underwriter.testnet
+    /// ```
+    pub fn list_sale(&mut self, title: ValidAccountId, price: U128, buyer: Option<ValidAccountId>) {
+        let underwriter = self.accounts.get(&title.to_string()).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+        assert!(!self.locks.contains_key(&title.to_string()), "Title is locked");
+        assert!(price.0 > 0, "Price must be greater than zero");
+
+        self.sales.insert(&title.to_string(), &Sale {
+            underwriter,
+            price,
+            buyer: buyer.map(|b| b.to_string()),
+        });
+        log!("Sale listed: {} for {}", title, price.0);
+    }
+
+    /// Remove a sale listing, title stays in escrow
+    ///
+    /// ```bash
+    /// near call _escrow_account_ cancel_sale '{"title": "some_account.testnet"}' --accountId underwriter.testnet
+    /// ```
+    pub fn cancel_sale(&mut self, title: ValidAccountId) {
+        let sale = self.sales.get(&title.to_string()).expect("No sale found");
+        assert_eq!(sale.underwriter, env::predecessor_account_id(), "Account does not control deed account");
+
+        self.sales.remove(&title.to_string());
+        log!("Sale canceled: {}", title);
+    }
+
+    /// Buy a listed title, attached deposit must cover the price.
+    /// The deed transfers ownership to new_key (plus any extra_keys), then underwriter is paid.
+    /// Any deposit above the price is refunded, the full price is refunded if the transfer fails.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ buy '{"title": "some_account.testnet", "new_key": "ed25591:PK_HERE"}' --accountId buyer.testnet --amount 10
+    /// ```
+    #[payable]
+    pub fn buy(
+        &mut self,
+        title: ValidAccountId,
+        new_key: Base58PublicKey,
+        extra_keys: Option<Vec<AccessKey>>,
+    ) -> Promise {
+        let acct_id = title.to_string();
+        let sale = self.sales.get(&acct_id).expect("No sale found");
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(sale.underwriter, underwriter, "Sale is no longer valid");
+        assert!(!self.locks.contains_key(&acct_id), "Title is locked");
+        if let Some(buyer) = sale.buyer {
+            assert_eq!(buyer, env::predecessor_account_id(), "Sale is reserved for another buyer");
+        }
+        let deposit = env::attached_deposit();
+        assert!(deposit >= sale.price.0, "Deposit must cover sale price");
+
+        self.internal_remove(&acct_id);
+        log!("Sale complete: {} to {}", &acct_id, env::predecessor_account_id());
+
+        // Return anything extra
+        if deposit > sale.price.0 {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - sale.price.0);
+        }
 
-        // Call the deed, to transfer ownership to new public key
-        ext_deed::change_ownership(
+        // Call the deed, to transfer ownership to new public key(s), then pay the underwriter
+        let transfer = ext_deed::change_ownership(
             new_key,
+            extra_keys,
             &acct_id,
             0,
             CLOSE_ESCROW_GAS_FEE,
-        )
+        );
+        let payout = Payout {
+            amount: sale.price,
+            recipient: underwriter.clone(),
+            refund_to: Some(env::predecessor_account_id()),
+        };
+        self.internal_settle(acct_id, underwriter, transfer, Some(payout))
+    }
+
+    /// Set royalties for a title, paid out of every sale settled through escrow.
+    /// Only the first underwriter to register the title can set them, once, while still holding it.
+    /// Total is capped by DAO policy (max_royalty_bps), at most 10 recipients each with a non-zero share.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ set_royalty '{"title": "some_account.testnet", "recipients": {"creator.testnet": 500}}' --accountId underwriter.testnet
+    /// ```
+    pub fn set_royalty(&mut self, title: ValidAccountId, recipients: BTreeMap<ValidAccountId, u32>) {
+        let acct_id = title.to_string();
+        let underwriter = self.accounts.get(&acct_id).expect("Account is not in escrow");
+        assert_eq!(underwriter, env::predecessor_account_id(), "Account does not control deed account");
+
+        let mut royalty = self.royalties.get(&acct_id).expect("No royalty record found");
+        assert_eq!(royalty.creator, underwriter, "Only the first underwriter can set royalties");
+        assert!(!royalty.is_set, "Royalties already set");
+
+        assert!(recipients.len() <= MAX_ROYALTY_RECIPIENTS, "Too many royalty recipients");
+        assert!(recipients.values().all(|bps| *bps > 0), "Royalty share must be greater than zero");
+        let total: u32 = recipients.values().sum();
+        assert!(total <= self.max_royalty_bps, "Royalties exceed maximum allowed");
+
+        royalty.recipients = recipients.into_iter().map(|(account_id, bps)| (account_id.to_string(), bps)).collect();
+        royalty.is_set = true;
+        self.royalties.insert(&acct_id, &royalty);
+        log!("Royalty set: {} {}bps", &acct_id, total);
+    }
+
+    /// Get the royalty record for a title
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_royalty '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_royalty(&self, title: ValidAccountId) -> Option<Royalty> {
+        self.royalties.get(&title.to_string())
+    }
+
+    /// Get the sale listing for a title
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_sale '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_sale(&self, title: ValidAccountId) -> Option<Sale> {
+        self.sales.get(&title.to_string())
     }
 
     /// Checks if an account is escrowed
@@ -160,6 +967,15 @@ impl Escrow {
         self.accounts.get(&title.to_string()).is_some()
     }
 
+    /// Checks if a title is locked, returning the account holding the lock
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_lock '{"title": "some_account.testnet"}'
+    /// ```
+    pub fn get_lock(&self, title: ValidAccountId) -> Option<AccountId> {
+        self.locks.get(&title.to_string())
+    }
+
     /// Get the owner for a specific title
     ///
     /// ```bash
@@ -169,20 +985,85 @@ impl Escrow {
         self.accounts.get(&title.to_string())
     }
 
+    /// Get the owner for many titles at once, in order
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_underwriters '{"titles": ["acme.testnet", "acme-app.testnet"]}'
+    /// ```
+    pub fn get_underwriters(&self, titles: Vec<ValidAccountId>) -> Vec<Option<AccountId>> {
+        titles.iter()
+            .map(|title| self.accounts.get(&title.to_string()))
+            .collect()
+    }
+
+    /// List all escrowed titles with their underwriter, paginated
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_titles '{"from_index": 0, "limit": 50}'
+    /// ```
+    pub fn get_titles(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<(AccountId, AccountId)> {
+        let titles = self.titles.as_vector();
+        let start = from_index.unwrap_or(0);
+        let end = core::cmp::min(start.saturating_add(limit.unwrap_or(50)), titles.len());
+
+        (start..end)
+            .filter_map(|i| titles.get(i))
+            .filter_map(|title| self.accounts.get(&title).map(|underwriter| (title, underwriter)))
+            .collect()
+    }
+
+    /// List escrowed titles for a single underwriter, paginated
+    ///
+    /// ```bash
+    /// near view _escrow_account_ get_titles_by_underwriter '{"underwriter": "underwriter.testnet", "from_index": 0, "limit": 50}'
+    /// ```
+    pub fn get_titles_by_underwriter(
+        &self,
+        underwriter: ValidAccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        let titles = match self.titles_by_underwriter.get(&underwriter.to_string()) {
+            Some(titles) => titles,
+            None => return vec![],
+        };
+        let start = from_index.unwrap_or(0);
+        let end = core::cmp::min(start.saturating_add(limit.unwrap_or(50)), titles.len());
+
+        (start..end)
+            .filter_map(|i| titles.as_vector().get(i))
+            .collect()
+    }
+
+    /// Returns custody stats: currently held, total registered, total closed
+    ///
+    /// ```bash
+    /// near view _escrow_account_ stats
+    /// ```
+    pub fn stats(&self) -> (u64, u64, u64) {
+        (
+            self.titles.len(),
+            self.total_registered,
+            self.total_closed,
+        )
+    }
+
     /// Gets the escrow settings
     ///
     /// ```bash
     /// near view _escrow_account_ get_settings
     /// ```
     pub fn get_settings(&self) -> (
-        AccountId,
+        Vec<AccountId>,
         AccountId,
         Option<AccountId>,
+        u32,
     ) {
         (
-            self.registrar.clone(),
+            self.registrars.to_vec(),
             self.factory.clone(),
             self.dao.clone(),
+            self.max_royalty_bps,
         )
     }
 
@@ -191,35 +1072,70 @@ impl Escrow {
     /// Can only be called by the DAO contract (if originally configured)
     ///
     /// ```bash
-    /// near call _escrow_account_ update_settings '{"dao": "dao.sputnik.testnet", "registrar": "registrar.alias.testnet"}' --accountId dao.sputnik.testnet
+    /// near call _escrow_account_ update_settings '{"dao": "dao.sputnik.testnet", "factory": "testnet", "max_royalty_bps": 1000}' --accountId dao.sputnik.testnet
     /// ```
     pub fn update_settings(
         &mut self,
         dao: Option<ValidAccountId>,
         factory: Option<ValidAccountId>,
-        registrar: Option<ValidAccountId>,
+        max_royalty_bps: Option<u32>,
     ) {
-        assert!(self.dao.is_some(), "No ownership, cannot change settings");
-        assert_eq!(self.dao.clone().unwrap(), env::predecessor_account_id(), "Callee must be dao contract");
-        
+        self.assert_dao();
+
         // Update each individual setting
-        if dao.is_some() { self.dao = Some(dao.unwrap().to_string()); }
-        if factory.is_some() { self.factory = factory.unwrap().to_string(); }
-        if registrar.is_some() { self.registrar = registrar.unwrap().to_string(); }
+        if let Some(dao) = dao { self.dao = Some(dao.to_string()); }
+        if let Some(factory) = factory { self.factory = factory.to_string(); }
+        if let Some(max_royalty_bps) = max_royalty_bps {
+            assert!(u128::from(max_royalty_bps) <= BPS_DENOMINATOR, "Royalty cap cannot exceed 100%");
+            self.max_royalty_bps = max_royalty_bps;
+        }
     }
 
-    /// Returns semver of this contract.
-    pub fn version(&self) -> String {
-        env!("CARGO_PKG_VERSION").to_string()
+    /// Authorize another registrar to lock & settle titles
+    /// Registrars must implement "is_auction_active", it is only asked about titles the registrar locked
+    /// when their underwriter withdraws, so a broken registrar cannot block withdrawing other titles.
+    /// Can only be called by the DAO contract (if originally configured)
+    ///
+    /// ```bash
+    /// near call _escrow_account_ add_registrar '{"registrar": "otc.nym.testnet"}' --accountId dao.sputnik.testnet
+    /// ```
+    pub fn add_registrar(&mut self, registrar: ValidAccountId) {
+        self.assert_dao();
+        self.registrars.insert(&registrar.to_string());
+        log!("Registrar added: {}", registrar);
     }
-}
+
+    /// Revoke a registrar, titles it still has locked remain settleable by it until unlocked
+    /// Can only be called by the DAO contract (if originally configured)
+    ///
+    /// ```bash
+    /// near call _escrow_account_ remove_registrar '{"registrar": "otc.nym.testnet"}' --accountId dao.sputnik.testnet
+    /// ```
+    pub fn remove_registrar(&mut self, registrar: ValidAccountId) {
+        self.assert_dao();
+        self.registrars.remove(&registrar.to_string());
+        log!("Registrar removed: {}", registrar);
+    }
+
+    /// Checks the caller is the configured DAO
+    fn assert_dao(&self) {
+        assert!(self.dao.is_some(), "No ownership, cannot change settings");
+        assert_eq!(self.dao.clone().unwrap(), env::predecessor_account_id(), "Callee must be dao contract");
+    }
+
+    /// Returns semver of this contract.
+    pub fn version(&self) -> String {
+        env!("CARGO_PKG_VERSION").to_string()
+    }
+}
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::test_utils::{accounts, testing_env_with_promise_results, VMContextBuilder};
     use near_sdk::json_types::{ValidAccountId};
     use near_sdk::MockedBlockchain;
     use near_sdk::{testing_env};
+    use std::convert::TryFrom;
 
     use super::*;
 
@@ -245,13 +1161,35 @@ mod tests {
         builder
     }
 
+    /// Registers the predecessor as a swept deed, through register & its callback
+    fn register_deed(contract: &mut Escrow, underwriter: AccountId) {
+        let (current, signer, title) = (env::current_account_id(), env::signer_account_id(), env::predecessor_account_id());
+        let (block_index, deposit) = (env::block_index(), env::attached_deposit());
+        contract.register(underwriter.clone());
+
+        let me = ValidAccountId::try_from(current).unwrap();
+        let context = get_context(me.clone(), me.clone(), me.clone(), Some(false));
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"true".to_vec()));
+        assert!(contract.register_callback(title.clone(), underwriter, U128::from(deposit)));
+
+        // back to the caller's context
+        let mut context = get_context(
+            me,
+            ValidAccountId::try_from(signer).unwrap(),
+            ValidAccountId::try_from(title).unwrap(),
+            Some(false),
+        );
+        context.block_index(block_index).attached_deposit(deposit);
+        testing_env!(context.build());
+    }
+
     #[test]
     fn test_init() {
         let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
         testing_env!(context.build());
         let contract = create_blank_escrow();
         assert_eq!(contract.factory, accounts(0).to_string());
-        assert_eq!(contract.registrar, accounts(1).to_string());
+        assert_eq!(contract.get_settings().0, vec![accounts(1).to_string()]);
     }
 
     #[test]
@@ -271,7 +1209,25 @@ mod tests {
         context = get_context(accounts(3), accounts(2), accounts(2), Some(false));
         testing_env!(context.build());
 
-        contract.register(accounts(2).to_string(), Some(true));
+        register_deed(&mut contract, accounts(2).to_string());
+        assert_eq!(contract.accounts.get(&accounts(2).to_string()), Some(accounts(2).to_string()));
+    }
+
+    #[test]
+    fn test_register_not_swept() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // the deed says its sweep did not succeed
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"false".to_vec()));
+        assert!(!contract.register_callback(accounts(2).to_string(), accounts(2).to_string(), U128::from(ONE_NEAR)));
+        assert!(contract.accounts.get(&accounts(2).to_string()).is_none());
+
+        // not a deed at all
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        assert!(!contract.register_callback(accounts(2).to_string(), accounts(2).to_string(), U128::from(ONE_NEAR)));
+        assert!(contract.accounts.get(&accounts(2).to_string()).is_none());
     }
 
     // #[test]
@@ -298,7 +1254,7 @@ mod tests {
         let context2 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
         testing_env!(context2.build());
 
-        contract.register(accounts(2).to_string(), Some(false));
+        register_deed(&mut contract, accounts(2).to_string());
 
         let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(true));
         testing_env!(context3.build());
@@ -306,6 +1262,1004 @@ mod tests {
         assert!(is_registered, "Needs to be registered");
     }
 
+    #[test]
+    fn test_close_escrow_to_account() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.close_escrow_to_account(accounts(4), accounts(2), None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+    }
+
+    #[test]
+    #[should_panic(expected = "Account does not control deed account")]
+    fn test_close_escrow_to_account_unauthorized() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context3.build());
+        contract.close_escrow_to_account(accounts(4), accounts(2), None);
+    }
+
+    #[test]
+    fn test_buy() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.list_sale(accounts(4), U128::from(ONE_NEAR), Some(accounts(2)));
+        assert_eq!(contract.get_sale(accounts(4)).unwrap().price, U128::from(ONE_NEAR));
+
+        let mut context4 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        context4.attached_deposit(ONE_NEAR);
+        testing_env!(context4.build());
+        contract.buy(accounts(4), Base58PublicKey(vec![0; 33]), None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+        assert!(contract.get_sale(accounts(4)).is_none(), "Sale must be removed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Sale is reserved for another buyer")]
+    fn test_buy_wrong_buyer() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.list_sale(accounts(4), U128::from(ONE_NEAR), Some(accounts(2)));
+
+        let mut context4 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context4.attached_deposit(ONE_NEAR);
+        testing_env!(context4.build());
+        contract.buy(accounts(4), Base58PublicKey(vec![0; 33]), None);
+    }
+
+    #[test]
+    fn test_withdraw_from_escrow() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        // unlocked, no registrar can be auctioning it
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.withdraw_from_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+    }
+
+    #[test]
+    fn test_withdraw_from_escrow_locked() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.withdraw_from_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None);
+        assert!(contract.in_escrow(accounts(4)), "Only removed once the locking registrar confirms");
+
+        // the lock outlived the auction
+        let context5 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context5.build(), PromiseResult::Successful(b"false".to_vec()));
+        contract.withdraw_callback(accounts(4).to_string(), accounts(5).to_string(), accounts(1).to_string(), Base58PublicKey(vec![0; 33]), None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+        assert_eq!(contract.get_lock(accounts(4)), None);
+    }
+
+    #[test]
+    fn test_withdraw_from_escrow_other_registrar() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        // a registrar without "is_auction_active" holds no lock, so it is never asked
+        let context3 = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context3.build());
+        contract.add_registrar(ValidAccountId::try_from("broken_near").unwrap());
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.withdraw_from_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction is active for this title")]
+    fn test_withdraw_from_escrow_active_auction() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context4.build(), PromiseResult::Successful(b"true".to_vec()));
+        contract.withdraw_callback(accounts(4).to_string(), accounts(5).to_string(), accounts(1).to_string(), Base58PublicKey(vec![0; 33]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction is active for this title")]
+    fn test_withdraw_from_escrow_check_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        // registrar check ran out of gas, must not fail open
+        let context4 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context4.build(), PromiseResult::Failed);
+        contract.withdraw_callback(accounts(4).to_string(), accounts(5).to_string(), accounts(1).to_string(), Base58PublicKey(vec![0; 33]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Title is locked")]
+    fn test_close_escrow_locked() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.close_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None, None);
+    }
+
+    #[test]
+    fn test_close_escrow_locked_by_registrar() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+        assert_eq!(contract.get_lock(accounts(4)), Some(accounts(1).to_string()));
+
+        // anyone can trigger registrar settlement, like finalize via cron
+        let context4 = get_context(accounts(3), accounts(2), accounts(1), Some(false));
+        testing_env!(context4.build());
+        contract.close_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None, None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+        assert!(contract.get_lock(accounts(4)).is_none(), "Lock must be released");
+    }
+
+    #[test]
+    fn test_transfer_underwriter() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.transfer_underwriter(accounts(4), accounts(2), Some(true));
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(5).to_string()));
+        assert_eq!(contract.get_pending_underwriter(accounts(4)), Some(accounts(2).to_string()));
+
+        let context4 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context4.build());
+        contract.accept_underwriter(accounts(4));
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(2).to_string()));
+        assert!(contract.get_pending_underwriter(accounts(4)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Title is locked")]
+    fn test_transfer_underwriter_locked() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.transfer_underwriter(accounts(4), accounts(2), None);
+    }
+
+    #[test]
+    fn test_get_titles() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // titles: Acct 0, 4, underwriters: Acct 5, 2
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context3.build());
+        register_deed(&mut contract, accounts(2).to_string());
+
+        assert_eq!(contract.get_titles(None, None).len(), 2);
+        assert_eq!(contract.get_titles(Some(1), Some(10)), vec![(accounts(0).to_string(), accounts(2).to_string())]);
+        assert_eq!(contract.get_titles_by_underwriter(accounts(5), None, None), vec![accounts(4).to_string()]);
+
+        // reassign, then close
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.transfer_underwriter(accounts(4), accounts(2), None);
+        assert!(contract.get_titles_by_underwriter(accounts(5), None, None).is_empty());
+        assert_eq!(contract.get_titles_by_underwriter(accounts(2), None, None).len(), 2);
+
+        let context5 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context5.build());
+        contract.close_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None, None);
+        assert_eq!(contract.stats(), (1, 2, 1));
+    }
+
+    #[test]
+    fn test_lock_additional_registrar() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+        contract.add_registrar(accounts(0));
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(0), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+        assert_eq!(contract.get_lock(accounts(4)), Some(accounts(0).to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Title is locked")]
+    fn test_close_escrow_locked_by_other_registrar() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+        contract.add_registrar(accounts(0));
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(0), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(1), Some(false));
+        testing_env!(context4.build());
+        contract.close_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None, None);
+    }
+
+    #[test]
+    fn test_settle_callback_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        context3.attached_deposit(ONE_NEAR);
+        testing_env!(context3.build());
+        let payout = Payout {
+            amount: U128::from(ONE_NEAR),
+            recipient: accounts(2).to_string(),
+            refund_to: None,
+        };
+        contract.close_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None, Some(payout.clone()));
+        assert!(!contract.in_escrow(accounts(4)));
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context4.build(), PromiseResult::Successful(b"false".to_vec()));
+        let transferred = contract.settle_callback(accounts(4).to_string(), accounts(5).to_string(), Some(payout));
+        assert!(!transferred);
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(5).to_string()), "Title must be restored");
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit must match payout amount")]
+    fn test_close_escrow_payout_deposit() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        let payout = Payout {
+            amount: U128::from(ONE_NEAR),
+            recipient: accounts(2).to_string(),
+            refund_to: None,
+        };
+        contract.close_escrow(accounts(4), Base58PublicKey(vec![0; 33]), None, Some(payout));
+    }
+
+    #[test]
+    fn test_set_royalty() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        let mut recipients = BTreeMap::new();
+        recipients.insert(accounts(5), 500);
+        contract.set_royalty(accounts(4), recipients);
+
+        let royalty = contract.get_royalty(accounts(4)).unwrap();
+        assert!(royalty.is_set);
+        assert_eq!(royalty.creator, accounts(5).to_string());
+        assert_eq!(royalty.recipients.get(&accounts(5).to_string()), Some(&500));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the first underwriter can set royalties")]
+    fn test_set_royalty_not_creator() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.transfer_underwriter(accounts(4), accounts(2), None);
+
+        let context4 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context4.build());
+        contract.set_royalty(accounts(4), BTreeMap::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalties exceed maximum allowed")]
+    fn test_set_royalty_cap() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        let mut recipients = BTreeMap::new();
+        recipients.insert(accounts(5), DEFAULT_MAX_ROYALTY_BPS + 1);
+        contract.set_royalty(accounts(4), recipients);
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalty share must be greater than zero")]
+    fn test_set_royalty_zero_share() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        let mut recipients = BTreeMap::new();
+        recipients.insert(accounts(5), 500);
+        recipients.insert(accounts(2), 0);
+        contract.set_royalty(accounts(4), recipients);
+    }
+
+    #[test]
+    fn test_nft_views() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // titles: Acct 4 & Acct 2, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context3.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(true));
+        testing_env!(context4.build());
+        let token = contract.nft_token(accounts(4).to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(5).to_string());
+        assert_eq!(token.metadata.unwrap().title, Some(accounts(4).to_string()));
+        assert!(contract.nft_token(accounts(1).to_string()).is_none());
+
+        assert_eq!(contract.nft_total_supply(), U128(2));
+        assert_eq!(contract.nft_tokens(Some(U128(1)), None).len(), 1);
+        assert_eq!(contract.nft_supply_for_owner(accounts(5)), U128(2));
+        assert_eq!(contract.nft_tokens_for_owner(accounts(5), None, Some(1)).len(), 1);
+        assert!(contract.nft_tokens_for_owner(accounts(1), None, None).is_empty());
+    }
+
+    #[test]
+    fn test_nft_approve_transfer() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.attached_deposit(1).build());
+        contract.nft_approve(accounts(4).to_string(), accounts(1), None);
+        assert!(contract.nft_is_approved(accounts(4).to_string(), accounts(1), Some(0)));
+
+        // approved marketplace moves the rights
+        let mut context4 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context4.attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), accounts(4).to_string(), Some(0), None);
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(2).to_string()));
+        assert!(!contract.nft_is_approved(accounts(4).to_string(), accounts(1), None), "Approvals must be cleared");
+    }
+
+    #[test]
+    #[should_panic(expected = "Title is locked")]
+    fn test_nft_transfer_locked() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context3.build());
+        contract.lock(accounts(4));
+
+        let mut context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), accounts(4).to_string(), None, None);
+    }
+
+    #[test]
+    fn test_nft_approved_close_escrow() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.attached_deposit(1).build());
+        contract.nft_approve(accounts(4).to_string(), accounts(0), None);
+
+        let context4 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context4.build());
+        contract.close_escrow_to_account(accounts(4), accounts(2), None);
+        assert!(!contract.in_escrow(accounts(4)), "Must be removed from escrow");
+    }
+
+    #[test]
+    fn test_nft_resolve_transfer_returned() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.attached_deposit(1).build());
+        contract.nft_approve(accounts(4).to_string(), accounts(0), None);
+        contract.nft_transfer_call(accounts(2), accounts(4).to_string(), None, None, "".to_string());
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(2).to_string()));
+
+        // receiver asks for the rights to be returned
+        let mut approved = HashMap::new();
+        approved.insert(accounts(0).to_string(), 0);
+        let context4 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context4.build(), PromiseResult::Successful(b"true".to_vec()));
+        let transferred = contract.nft_resolve_transfer(accounts(5).to_string(), accounts(2).to_string(), accounts(4).to_string(), Some(approved));
+        assert!(!transferred);
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(5).to_string()));
+        assert!(contract.nft_is_approved(accounts(4).to_string(), accounts(0), Some(0)), "Approvals must be restored");
+    }
+
+    #[test]
+    fn test_loan_repay() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5, lender: Acct 0
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context3.attached_deposit(ONE_NEAR).build());
+        contract.offer_loan(accounts(4), U128(ONE_NEAR * 2), 100);
+        assert_eq!(contract.get_loan_offers(accounts(4)).len(), 1);
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.accept_loan(accounts(4), accounts(0), U128(ONE_NEAR), U128(ONE_NEAR * 2), 100);
+        assert_eq!(contract.get_lock(accounts(4)), Some(accounts(3).to_string()), "Escrow must hold the lock");
+        assert_eq!(contract.get_loan(accounts(4)).unwrap().due_block, 100);
+
+        let mut context5 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context5.attached_deposit(ONE_NEAR * 2).build());
+        contract.repay_loan(accounts(4));
+        assert!(contract.get_loan(accounts(4)).is_none());
+        assert!(contract.get_lock(accounts(4)).is_none());
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(5).to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Loan offer terms changed")]
+    fn test_loan_offer_replaced() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context3.attached_deposit(ONE_NEAR).build());
+        contract.offer_loan(accounts(4), U128(ONE_NEAR * 2), 100);
+        // lender front-runs the underwriter with a higher repayment
+        contract.offer_loan(accounts(4), U128(ONE_NEAR * 3), 100);
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.accept_loan(accounts(4), accounts(0), U128(ONE_NEAR), U128(ONE_NEAR * 2), 100);
+    }
+
+    #[test]
+    fn test_loan_default() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context3.attached_deposit(ONE_NEAR).build());
+        contract.offer_loan(accounts(4), U128(ONE_NEAR * 2), 100);
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.accept_loan(accounts(4), accounts(0), U128(ONE_NEAR), U128(ONE_NEAR * 2), 100);
+
+        let mut context5 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context5.block_index(101).build());
+        contract.claim_default(accounts(4));
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(0).to_string()));
+        assert!(contract.get_lock(accounts(4)).is_none());
+    }
+
+    #[test]
+    fn test_loan_offers_full() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        for i in 0..MAX_LOAN_OFFERS {
+            let lender = ValidAccountId::try_from(format!("lender{}.near", i)).unwrap();
+            let mut context3 = get_context(accounts(3), lender.clone(), lender, Some(false));
+            testing_env!(context3.attached_deposit(ONE_NEAR + i as u128).build());
+            contract.offer_loan(accounts(4), U128(ONE_NEAR * 2), 100);
+        }
+
+        // bigger offer replaces the smallest one
+        let mut context4 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context4.attached_deposit(ONE_NEAR * 2).build());
+        contract.offer_loan(accounts(4), U128(ONE_NEAR * 2), 100);
+        let offers = contract.get_loan_offers(accounts(4));
+        assert_eq!(offers.len(), MAX_LOAN_OFFERS);
+        assert!(offers.iter().all(|o| o.lender != "lender0.near"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Loan is not past due")]
+    fn test_loan_default_not_due() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let mut context3 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context3.attached_deposit(ONE_NEAR).build());
+        contract.offer_loan(accounts(4), U128(ONE_NEAR * 2), 100);
+
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        contract.accept_loan(accounts(4), accounts(0), U128(ONE_NEAR), U128(ONE_NEAR * 2), 100);
+
+        let context5 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context5.build());
+        contract.claim_default(accounts(4));
+    }
+
+    fn lease_permission() -> FunctionCallPermission {
+        FunctionCallPermission {
+            receiver_id: accounts(1).to_string(),
+            method_names: vec!["play".to_string()],
+            allowance: None,
+        }
+    }
+
+    #[test]
+    fn test_lease() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5, renter: Acct 0
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.list_lease(accounts(4), U128(ONE_NEAR), 100, lease_permission());
+
+        let mut context4 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context4.attached_deposit(ONE_NEAR).build());
+        contract.rent(accounts(4), Base58PublicKey(vec![0; 33]));
+        assert_eq!(contract.get_lock(accounts(4)), Some(accounts(3).to_string()), "Escrow must hold the lock");
+
+        let context5 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context5.build(), PromiseResult::Successful(b"true".to_vec()));
+        assert!(contract.lease_callback(accounts(4).to_string()));
+        let lease = contract.get_lease(accounts(4)).unwrap();
+        assert!(lease.is_active);
+        assert_eq!(lease.expires_block, 100);
+
+        let mut context6 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context6.block_index(101).build());
+        contract.end_lease(accounts(4));
+
+        let context7 = get_context(accounts(3), accounts(2), accounts(3), Some(false));
+        testing_env_with_promise_results(context7.build(), PromiseResult::Successful(vec![]));
+        contract.end_lease_callback(accounts(4).to_string());
+        assert!(contract.get_lease(accounts(4)).is_none());
+        assert!(contract.get_lock(accounts(4)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Lease key cannot call the title account")]
+    fn test_list_lease_title_receiver() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.list_lease(accounts(4), U128(ONE_NEAR), 100, FunctionCallPermission {
+            receiver_id: accounts(4).to_string(),
+            method_names: vec![],
+            allowance: None,
+        });
+    }
+
+    #[test]
+    fn test_lease_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.list_lease(accounts(4), U128(ONE_NEAR), 100, lease_permission());
+
+        let mut context4 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context4.attached_deposit(ONE_NEAR).build());
+        contract.rent(accounts(4), Base58PublicKey(vec![0; 33]));
+
+        let context5 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context5.build(), PromiseResult::Failed);
+        assert!(!contract.lease_callback(accounts(4).to_string()));
+        assert!(contract.get_lease(accounts(4)).is_none());
+        assert!(contract.get_lock(accounts(4)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Lease has not expired")]
+    fn test_end_lease_not_expired() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context3 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context3.build());
+        contract.list_lease(accounts(4), U128(ONE_NEAR), 100, lease_permission());
+
+        let mut context4 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context4.attached_deposit(ONE_NEAR).build());
+        contract.rent(accounts(4), Base58PublicKey(vec![0; 33]));
+
+        let context5 = get_context(accounts(3), accounts(5), accounts(3), Some(false));
+        testing_env_with_promise_results(context5.build(), PromiseResult::Successful(b"true".to_vec()));
+        contract.lease_callback(accounts(4).to_string());
+
+        let context6 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context6.build());
+        contract.end_lease(accounts(4));
+    }
+
+    #[test]
+    fn test_lock_many() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // title: Acct 4, underwriter: Acct 5, Acct 2 not in escrow
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        assert_eq!(
+            contract.get_underwriters(vec![accounts(4), accounts(2)]),
+            vec![Some(accounts(5).to_string()), None]
+        );
+
+        let context3 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context3.build());
+        assert_eq!(contract.lock_many(vec![accounts(4), accounts(2)]), vec![true, false]);
+        assert_eq!(contract.lock_many(vec![accounts(4)]), vec![false], "Already locked");
+        assert_eq!(contract.get_lock(accounts(4)), Some(accounts(1).to_string()));
+    }
+
+    #[test]
+    fn test_close_escrow_lot_partial() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // titles: Acct 4 & Acct 2, underwriter: Acct 5, winner: Acct 0
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context3.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context4 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context4.build());
+        contract.lock_many(vec![accounts(4), accounts(2)]);
+        contract.close_escrow_lot(vec![accounts(4), accounts(2)], accounts(0), None);
+        assert!(!contract.in_escrow(accounts(4)));
+        assert!(!contract.in_escrow(accounts(2)));
+
+        let context5 = get_context(accounts(3), accounts(1), accounts(3), Some(false));
+        testing_env_with_promise_results(context5.build(), PromiseResult::Failed);
+        assert!(!contract.settle_lot_callback(
+            vec![accounts(4).to_string(), accounts(2).to_string()],
+            vec![accounts(5).to_string(), accounts(5).to_string()],
+            None,
+        ));
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(5).to_string()), "Title must be restored");
+        assert_eq!(contract.get_underwriter(accounts(2)), Some(accounts(5).to_string()), "Title must be restored");
+    }
+
+    #[test]
+    fn test_close_escrow_lot_one_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // titles: Acct 4 & Acct 2, underwriter: Acct 5, winner: Acct 0
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context3.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        let context4 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context4.build());
+        contract.lock_many(vec![accounts(4), accounts(2)]);
+        contract.close_escrow_lot(vec![accounts(4), accounts(2)], accounts(0), None);
+
+        // first deed reserved, second failed: nothing moves
+        let context5 = get_context(accounts(3), accounts(1), accounts(3), Some(false));
+        testing_env!(
+            context5.build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed]
+        );
+        assert!(!contract.settle_lot_callback(
+            vec![accounts(4).to_string(), accounts(2).to_string()],
+            vec![accounts(5).to_string(), accounts(5).to_string()],
+            None,
+        ));
+        assert_eq!(contract.get_underwriter(accounts(4)), Some(accounts(5).to_string()), "Title must be restored");
+        assert_eq!(contract.get_underwriter(accounts(2)), Some(accounts(5).to_string()), "Title must be restored");
+    }
+
+    #[test]
+    fn test_close_escrow_lot() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        // titles: Acct 4 & Acct 2, underwriter: Acct 5, winner: Acct 0
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context3.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        // only Acct 4 carries a royalty
+        let context4 = get_context(accounts(3), accounts(5), accounts(5), Some(false));
+        testing_env!(context4.build());
+        let mut recipients = BTreeMap::new();
+        recipients.insert(accounts(5), 500);
+        contract.set_royalty(accounts(4), recipients);
+
+        let context5 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context5.build());
+        contract.lock_many(vec![accounts(4), accounts(2)]);
+        contract.close_escrow_lot(vec![accounts(4), accounts(2)], accounts(0), None);
+
+        let context6 = get_context(accounts(3), accounts(1), accounts(3), Some(false));
+        testing_env!(
+            context6.build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Successful(vec![])]
+        );
+        assert!(contract.settle_lot_callback(
+            vec![accounts(4).to_string(), accounts(2).to_string()],
+            vec![accounts(5).to_string(), accounts(5).to_string()],
+            Some(Payout {
+                amount: U128::from(ONE_NEAR * 10),
+                recipient: accounts(5).to_string(),
+                refund_to: Some(accounts(0).to_string()),
+            }),
+        ));
+        assert!(!contract.in_escrow(accounts(4)));
+        assert!(!contract.in_escrow(accounts(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account does not control deed account")]
+    fn test_close_escrow_lot_unlocked_title() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_escrow();
+
+        let context2 = get_context(accounts(3), accounts(4), accounts(4), Some(false));
+        testing_env!(context2.build());
+        register_deed(&mut contract, accounts(5).to_string());
+        let context3 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context3.build());
+        register_deed(&mut contract, accounts(5).to_string());
+
+        // only one title locked by the registrar, so no title in the lot can close
+        let context4 = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        testing_env!(context4.build());
+        contract.lock(accounts(4));
+        contract.close_escrow_lot(vec![accounts(4), accounts(2)], accounts(0), None);
+    }
+
     // #[test]
     // fn test_register() {
     //     let context = get_context(accounts(0), Some(usize::from(u8::from(0))));
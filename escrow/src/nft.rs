@@ -0,0 +1,385 @@
+use crate::*;
+
+pub const NFT_METADATA_SPEC: &str = "nft-1.0.0";
+const NFT_ON_TRANSFER_GAS_FEE: u64 = 25_000_000_000_000; // 25 Tgas
+const NFT_RESOLVE_TRANSFER_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const NFT_ON_APPROVE_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+
+#[ext_contract(ext_nft_receiver)]
+pub trait NonFungibleTokenReceiver {
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: AccountId,
+        msg: String,
+    ) -> bool;
+}
+
+#[ext_contract(ext_approval_receiver)]
+pub trait NonFungibleTokenApprovalReceiver {
+    fn nft_on_approve(
+        &mut self,
+        token_id: AccountId,
+        owner_id: AccountId,
+        approval_id: u64,
+        msg: String,
+    );
+}
+
+/// NEP-177 contract level metadata
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NFTContractMetadata {
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+    pub icon: Option<String>,
+    pub base_uri: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+/// NEP-177 token metadata, derived from the escrowed title
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    pub copies: Option<u64>,
+    pub issued_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub starts_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub extra: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+/// NEP-171 token, token_id is the escrowed account & owner_id is the underwriter
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonToken {
+    pub token_id: AccountId,
+    pub owner_id: AccountId,
+    pub metadata: Option<TokenMetadata>,
+    pub approved_account_ids: Option<HashMap<AccountId, u64>>,
+}
+
+#[near_bindgen]
+impl Escrow {
+    /// NEP-177 metadata for the escrow collection
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_metadata
+    /// ```
+    pub fn nft_metadata(&self) -> NFTContractMetadata {
+        NFTContractMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: "Nym Escrowed Titles".to_string(),
+            symbol: "NYM".to_string(),
+            icon: None,
+            base_uri: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    /// Get an escrowed title as an NFT
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_token '{"token_id": "some_account.testnet"}'
+    /// ```
+    pub fn nft_token(&self, token_id: AccountId) -> Option<JsonToken> {
+        self.accounts
+            .get(&token_id)
+            .map(|owner_id| self.internal_json_token(token_id, owner_id))
+    }
+
+    /// Total number of titles held in escrow
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_total_supply
+    /// ```
+    pub fn nft_total_supply(&self) -> U128 {
+        U128(self.titles.len() as u128)
+    }
+
+    /// List all escrowed titles as NFTs, paginated
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_tokens '{"from_index": "0", "limit": 50}'
+    /// ```
+    pub fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonToken> {
+        let from_index = from_index.map(|i| i.0 as u64);
+        self.get_titles(from_index, limit)
+            .into_iter()
+            .map(|(token_id, owner_id)| self.internal_json_token(token_id, owner_id))
+            .collect()
+    }
+
+    /// Number of titles underwritten by an account
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_supply_for_owner '{"account_id": "underwriter.testnet"}'
+    /// ```
+    pub fn nft_supply_for_owner(&self, account_id: ValidAccountId) -> U128 {
+        let supply = self
+            .titles_by_underwriter
+            .get(&account_id.to_string())
+            .map(|titles| titles.len())
+            .unwrap_or(0);
+        U128(supply as u128)
+    }
+
+    /// List titles underwritten by an account as NFTs, paginated
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_tokens_for_owner '{"account_id": "underwriter.testnet", "from_index": "0", "limit": 50}'
+    /// ```
+    pub fn nft_tokens_for_owner(
+        &self,
+        account_id: ValidAccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<JsonToken> {
+        let owner_id = account_id.to_string();
+        let from_index = from_index.map(|i| i.0 as u64);
+        self.get_titles_by_underwriter(account_id, from_index, limit)
+            .into_iter()
+            .map(|token_id| self.internal_json_token(token_id, owner_id.clone()))
+            .collect()
+    }
+
+    fn internal_json_token(&self, token_id: AccountId, owner_id: AccountId) -> JsonToken {
+        let metadata = TokenMetadata {
+            title: Some(token_id.clone()),
+            description: Some(format!("Escrowed account {}, underwritten by {}", &token_id, &owner_id)),
+            media: None,
+            media_hash: None,
+            copies: Some(1),
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        };
+
+        let approved_account_ids = self.approvals.get(&token_id).unwrap_or_default();
+        JsonToken {
+            token_id,
+            owner_id,
+            metadata: Some(metadata),
+            approved_account_ids: Some(approved_account_ids),
+        }
+    }
+
+    /// Move underwriting rights to another account, the title stays in escrow.
+    /// Callable by the underwriter or an approved account, not while the title is locked.
+    /// Escrow remains the only account able to rekey the deed.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ nft_transfer '{"receiver_id": "other_wallet.testnet", "token_id": "some_account.testnet"}' --accountId underwriter.testnet --depositYocto 1
+    /// ```
+    #[payable]
+    pub fn nft_transfer(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: AccountId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.internal_nft_transfer(&receiver_id.to_string(), &token_id, approval_id, memo);
+    }
+
+    /// Transfer underwriting rights, then notify the receiver with nft_on_transfer.
+    /// If the receiver returns true (or fails), the rights return to the previous underwriter.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ nft_transfer_call '{"receiver_id": "market.testnet", "token_id": "some_account.testnet", "msg": ""}' --accountId underwriter.testnet --depositYocto 1 --gas 100000000000000
+    /// ```
+    #[payable]
+    pub fn nft_transfer_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: AccountId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> Promise {
+        assert_one_yocto();
+        let receiver_id = receiver_id.to_string();
+        let (previous_owner_id, approved_account_ids) = self.internal_nft_transfer(&receiver_id, &token_id, approval_id, memo);
+
+        ext_nft_receiver::nft_on_transfer(
+            env::predecessor_account_id(),
+            previous_owner_id.clone(),
+            token_id.clone(),
+            msg,
+            &receiver_id,
+            0,
+            NFT_ON_TRANSFER_GAS_FEE,
+        )
+        .then(ext_self::nft_resolve_transfer(
+            previous_owner_id,
+            receiver_id.clone(),
+            token_id,
+            approved_account_ids,
+            &env::current_account_id(),
+            0,
+            NFT_RESOLVE_TRANSFER_GAS_FEE,
+        ))
+    }
+
+    /// Resolve Transfer Callback
+    /// Returns the rights to the previous underwriter if the receiver asked for it (or failed),
+    /// as long as the receiver still holds them. Returns true if the transfer stands.
+    #[private]
+    pub fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: AccountId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        let must_return = match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice::<bool>(&result).unwrap_or(true),
+            PromiseResult::Failed => true,
+            PromiseResult::NotReady => unreachable!(),
+        };
+        if !must_return {
+            return true;
+        }
+
+        // Receiver may have moved it on, or the title may have left escrow
+        if self.accounts.get(&token_id) != Some(receiver_id) {
+            return true;
+        }
+
+        self.internal_set_underwriter(&token_id, &previous_owner_id);
+        if let Some(approved_account_ids) = approved_account_ids {
+            self.approvals.insert(&token_id, &approved_account_ids);
+        }
+        log!("Transfer returned: {} {}", &token_id, &previous_owner_id);
+        false
+    }
+
+    /// Approve an account (e.g. a marketplace) to transfer or settle a title on the underwriter's behalf.
+    /// If msg is given, the approved account is notified with nft_on_approve.
+    ///
+    /// ```bash
+    /// near call _escrow_account_ nft_approve '{"token_id": "some_account.testnet", "account_id": "market.testnet"}' --accountId underwriter.testnet --depositYocto 1
+    /// ```
+    #[payable]
+    pub fn nft_approve(
+        &mut self,
+        token_id: AccountId,
+        account_id: ValidAccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        assert!(env::attached_deposit() > 0, "Requires attached deposit of at least 1 yoctoNEAR");
+        let owner_id = self.accounts.get(&token_id).expect("Account is not in escrow");
+        assert_eq!(owner_id, env::predecessor_account_id(), "Account does not control deed account");
+
+        let approval_id = self.next_approval_id;
+        self.next_approval_id += 1;
+        let mut approved = self.approvals.get(&token_id).unwrap_or_default();
+        approved.insert(account_id.to_string(), approval_id);
+        self.approvals.insert(&token_id, &approved);
+        log!("Approved: {} {} {}", &token_id, &account_id, approval_id);
+
+        msg.map(|msg| {
+            ext_approval_receiver::nft_on_approve(
+                token_id,
+                owner_id,
+                approval_id,
+                msg,
+                account_id.as_ref(),
+                0,
+                NFT_ON_APPROVE_GAS_FEE,
+            )
+        })
+    }
+
+    /// Revoke a single approved account
+    ///
+    /// ```bash
+    /// near call _escrow_account_ nft_revoke '{"token_id": "some_account.testnet", "account_id": "market.testnet"}' --accountId underwriter.testnet --depositYocto 1
+    /// ```
+    #[payable]
+    pub fn nft_revoke(&mut self, token_id: AccountId, account_id: ValidAccountId) {
+        assert_one_yocto();
+        let owner_id = self.accounts.get(&token_id).expect("Account is not in escrow");
+        assert_eq!(owner_id, env::predecessor_account_id(), "Account does not control deed account");
+
+        if let Some(mut approved) = self.approvals.get(&token_id) {
+            approved.remove(account_id.as_ref());
+            self.approvals.insert(&token_id, &approved);
+        }
+    }
+
+    /// Revoke all approved accounts
+    ///
+    /// ```bash
+    /// near call _escrow_account_ nft_revoke_all '{"token_id": "some_account.testnet"}' --accountId underwriter.testnet --depositYocto 1
+    /// ```
+    #[payable]
+    pub fn nft_revoke_all(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        let owner_id = self.accounts.get(&token_id).expect("Account is not in escrow");
+        assert_eq!(owner_id, env::predecessor_account_id(), "Account does not control deed account");
+
+        self.approvals.remove(&token_id);
+    }
+
+    /// Check if an account is approved for a title, optionally for a specific approval id
+    ///
+    /// ```bash
+    /// near view _escrow_account_ nft_is_approved '{"token_id": "some_account.testnet", "approved_account_id": "market.testnet"}'
+    /// ```
+    pub fn nft_is_approved(
+        &self,
+        token_id: AccountId,
+        approved_account_id: ValidAccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        self.internal_is_approved(&token_id, approved_account_id.as_ref(), approval_id)
+    }
+
+    pub(crate) fn internal_is_approved(&self, token_id: &AccountId, account_id: &AccountId, approval_id: Option<u64>) -> bool {
+        match self.approvals.get(token_id).and_then(|approved| approved.get(account_id).copied()) {
+            Some(id) => approval_id.is_none_or(|approval_id| approval_id == id),
+            None => false,
+        }
+    }
+
+    /// Checks the caller may move the title, then assigns the new underwriter.
+    /// Returns the previous underwriter & approvals, so a failed transfer_call can restore them.
+    fn internal_nft_transfer(
+        &mut self,
+        receiver_id: &AccountId,
+        token_id: &AccountId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+        let owner_id = self.accounts.get(token_id).expect("Account is not in escrow");
+        assert!(!self.locks.contains_key(token_id), "Title is locked");
+        let sender_id = env::predecessor_account_id();
+        if sender_id != owner_id {
+            assert!(self.internal_is_approved(token_id, &sender_id, approval_id), "Unauthorized access, underwriter or approved account only");
+        }
+        assert_ne!(&owner_id, receiver_id, "Current and next owner must differ");
+
+        let approved_account_ids = self.approvals.get(token_id);
+        self.internal_set_underwriter(token_id, receiver_id);
+        if let Some(memo) = memo {
+            log!("Memo: {}", memo);
+        }
+        (owner_id, approved_account_ids)
+    }
+}
@@ -2,7 +2,7 @@ use near_sdk::{
     near_bindgen,
     ext_contract,
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::{ UnorderedMap, TreeMap},
+    collections::{ LookupMap, UnorderedMap, TreeMap},
     json_types::{ ValidAccountId, Base58PublicKey, Base64VecU8, U128 },
     serde_json::json,
     serde::{Deserialize, Serialize},
@@ -11,6 +11,7 @@ use near_sdk::{
     BlockHeight,
     PanicOnDefault,
     Promise,
+    PromiseResult,
     PublicKey,
     env,
     log,
@@ -19,6 +20,14 @@ use near_sdk::{
     Gas,
 };
 use bs58;
+use std::collections::HashMap;
+
+mod lots;
+mod offers;
+mod pools;
+pub use crate::lots::*;
+pub use crate::offers::*;
+pub use crate::pools::*;
 
 near_sdk::setup_alloc!();
 
@@ -26,18 +35,72 @@ pub const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
 // const AUCTION_STORAGE_COST: u128 = 2_000_000_000_000_000_000_000;
 // const ACCESS_KEY_ALLOWANCE: u128 = 1_000_000_000_000_000_000_000;
 const CHECK_UNDERWRITER_GAS_FEE: u64 = 5_000_000_000_000; // 5 Tgas
-const CREATE_CALLBACK_GAS_FEE: u64 = 50_000_000_000_000; // 50 Tgas
-const CLOSE_ESCROW_GAS_FEE: u64 = 50_000_000_000_000; // 50 Tgas
+const LOCK_GAS_FEE: u64 = 5_000_000_000_000; // 5 Tgas
+// a refused lock may also remove the auction's cron task
+const LOCK_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000 + CRON_REMOVE_GAS_FEE; // 20 Tgas
+// a refused lot lock unlocks every title already locked
+const LOCK_LOT_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000 + MAX_LOT_SIZE * LOCK_GAS_FEE; // 35 Tgas
+const CALLBACK_BASE_GAS_FEE: u64 = 50_000_000_000_000; // 50 Tgas
+// covers the lock & cron task the callback schedules, on top of its own execution
+const CREATE_CALLBACK_GAS_FEE: u64 = CALLBACK_BASE_GAS_FEE
+    + LOCK_GAS_FEE
+    + LOCK_CALLBACK_GAS_FEE
+    + CRON_CREATE_GAS_FEE
+    + CRON_CALLBACK_GAS_FEE; // 125 Tgas
+const CLOSE_ESCROW_GAS_FEE: u64 = 60_000_000_000_000; // 60 Tgas
+const CLOSE_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const ACCEPT_OFFER_CALLBACK_GAS_FEE: u64 = CALLBACK_BASE_GAS_FEE + CLOSE_ESCROW_GAS_FEE + CLOSE_CALLBACK_GAS_FEE; // 120 Tgas
+const LOCK_MANY_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const CREATE_AUCTION_GAS_FEE: u64 = 12_000_000_000_000; // 12 Tgas, per auction in a batch
+// the auctions themselves, their lock & up to one cron task per MIN_TASK_BATCH_SIZE auctions
+const CREATE_BATCH_CALLBACK_GAS_FEE: u64 = 20_000_000_000_000
+    + MAX_BATCH_SIZE * CREATE_AUCTION_GAS_FEE
+    + LOCK_MANY_GAS_FEE
+    + LOCK_CALLBACK_GAS_FEE
+    + MAX_BATCH_TASKS * (CRON_CREATE_GAS_FEE + CRON_CALLBACK_GAS_FEE); // 272 Tgas
+// a refused close also unlocks the title
+const CLOSE_AUCTION_CALLBACK_GAS_FEE: u64 = CLOSE_CALLBACK_GAS_FEE + LOCK_GAS_FEE; // 15 Tgas
+const FINALIZE_GAS_FEE: u64 = 10_000_000_000_000 + CLOSE_ESCROW_GAS_FEE + CLOSE_AUCTION_CALLBACK_GAS_FEE; // 85 Tgas, per auction
+const FINALIZE_BASE_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
+const CLOSE_LOT_GAS_FEE: u64 = 200_000_000_000_000; // 200 Tgas
+// a refused lot close unlocks every title
+const FINALIZE_LOT_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000 + MAX_LOT_SIZE * LOCK_GAS_FEE; // 35 Tgas
+const MAX_LOT_SIZE: u64 = 5;
+const MIN_POOL_CONTRIBUTION: u128 = ONE_NEAR / 10;
+const MIN_OFFER: u128 = ONE_NEAR / 10;
+const MAX_OFFERS: usize = 10;
+const MAX_POOL_CONTRIBUTORS: u64 = 20;
+const MAX_POOLS: u64 = 5;
+const BPS_DENOMINATOR: u128 = 10_000;
 const CLOSE_BLOCK_OFFSET: u64 = 600_000; // ~7 days
 const REVEAL_BLOCK_OFFSET: u64 = 260_000; // ~3 days
-
-// TODO: Cron fee & schedule setup
+const CRON_TASK_GAS: u64 = 230_000_000_000_000; // 230 Tgas
+// croncat attaches its own gas to the proxied call, so tasks stay well under 300 Tgas
+const MAX_CRON_GAS: u64 = 250_000_000_000_000; // 250 Tgas
+// every cron task can finalize at least MIN_TASK_BATCH_SIZE auctions
+const MIN_TASK_BATCH_SIZE: u64 = 2;
+const MIN_CRON_GAS: u64 = FINALIZE_BASE_GAS_FEE + MIN_TASK_BATCH_SIZE * FINALIZE_GAS_FEE; // 180 Tgas
+// bound by what one transaction can create & schedule, see CREATE_BATCH_CALLBACK_GAS_FEE
+const MAX_BATCH_SIZE: u64 = 6;
+// a batch is split over cron tasks, each finalizing as many auctions as its gas allows
+const MAX_BATCH_TASKS: u64 = MAX_BATCH_SIZE.div_ceil(MIN_TASK_BATCH_SIZE);
+const CRON_CREATE_GAS_FEE: u64 = 30_000_000_000_000; // 30 Tgas
+// a task confirmed for a removed or re-created auction is removed again
+const CRON_CALLBACK_GAS_FEE: u64 = 10_000_000_000_000 + CRON_REMOVE_GAS_FEE; // 20 Tgas
+const CRON_REMOVE_GAS_FEE: u64 = 10_000_000_000_000; // 10 Tgas
 
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKeys {
     Auctions,
     Bids,
     Reveals,
+    Offers,
+    Pools,
+    AuctionBids { auction_id: u64 },
+    AuctionReveals { auction_id: u64 },
+    Lots,
+    LotTitles,
+    LotBids { lot_id: u64 },
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq)]
@@ -92,39 +155,118 @@ pub trait ExtRegistrar {
         title: ValidAccountId,
         signer: AccountId,
         auction_close_block: Option<BlockHeight>,
-        is_blind: Option<bool>,
+        options: AuctionOptions,
         #[callback]
         underwriter: Option<AccountId>,
     );
-    fn cron_callback(
-        &self,
+    fn cron_callback(&mut self, id: AccountId, nonce: u64);
+    fn cron_batch_callback(&mut self, ids: Vec<(AccountId, u64)>);
+    fn lock_callback(&mut self, title: AccountId);
+    fn create_batch_callback(
+        &mut self,
+        titles: Vec<ValidAccountId>,
+        signer: AccountId,
+        auction_close_block: Option<BlockHeight>,
+        is_blind: Option<bool>,
+        #[callback]
+        underwriters: Vec<Option<AccountId>>,
+    ) -> Vec<(AccountId, bool)>;
+    fn lock_many_callback(&mut self, titles: Vec<AccountId>);
+    fn create_lot_callback(
+        &mut self,
+        titles: Vec<ValidAccountId>,
+        signer: AccountId,
+        auction_close_block: Option<BlockHeight>,
+        #[callback]
+        underwriters: Vec<Option<AccountId>>,
+    ) -> u64;
+    fn lock_lot_callback(&mut self, lot_id: u64);
+    fn finalize_lot_callback(&mut self, titles: Vec<AccountId>, winner_id: AccountId, amount: U128) -> bool;
+    fn accept_offer_callback(
+        &mut self,
+        title: ValidAccountId,
+        signer: AccountId,
         #[callback]
-        task_hash: Base64VecU8
+        underwriter: Option<AccountId>,
+        #[callback]
+        lock: Option<AccountId>,
     );
+    fn close_offer_callback(&mut self, title: AccountId) -> bool;
+    fn close_auction_callback(&mut self, title: AccountId, refund_to: AccountId, amount: U128) -> bool;
+}
+
+/// Funds escrow releases to recipient once the title transfer is confirmed, or refunds on failure
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub amount: U128,
+    pub recipient: AccountId,
+    pub refund_to: Option<AccountId>,
 }
 
 #[ext_contract(ext_escrow)]
 pub trait ExtEscrow {
     fn get_underwriter(&self, title: ValidAccountId) -> Option<AccountId>;
-    fn close_escrow(&mut self, title: AccountId, new_key: PublicKey) -> Promise;
+    fn get_underwriters(&self, titles: Vec<ValidAccountId>) -> Vec<Option<AccountId>>;
+    fn get_lock(&self, title: ValidAccountId) -> Option<AccountId>;
+    fn lock(&mut self, title: AccountId);
+    fn lock_many(&mut self, titles: Vec<AccountId>) -> Vec<bool>;
+    fn unlock(&mut self, title: AccountId);
+    fn close_escrow(&mut self, title: AccountId, new_key: Base58PublicKey, payout: Option<Payout>) -> Promise;
+    fn close_escrow_to_account(&mut self, title: AccountId, claimant: AccountId, payout: Option<Payout>) -> Promise;
+    fn close_escrow_lot(
+        &mut self,
+        titles: Vec<AccountId>,
+        winner: AccountId,
+        payout: Option<Payout>,
+    ) -> Promise;
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, PanicOnDefault)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Bid {
     amount: Balance,
-    pk: PublicKey,
+    // when no key is given, the winner claims the title from the deed by account
+    pk: Option<PublicKey>,
     precommit: Option<Vec<u8>>
 }
 
+/// Opt-in policy to automatically start a new round when an auction ends unsold
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RelistPolicy {
+    /// Rounds left after this one
+    pub retries: u32,
+    /// Reserve reduction per round, in basis points
+    pub decay_bps: u32,
+}
+
+/// Optional settings an auction is created with, carried through to the create callback
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuctionOptions {
+    pub is_blind: Option<bool>,
+    pub reserve: Option<U128>,
+    pub relist: Option<RelistPolicy>,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Auction {
     pub title: AccountId,
+    /// Creation number, tells a re-created auction for the same title apart
+    pub nonce: u64,
     pub is_blind: bool,
     pub underwriter: Option<AccountId>,
     pub winner_id: Option<AccountId>,
     pub close_block: Option<BlockHeight>,
     pub cron_hash: Option<Base64VecU8>,
+    /// Settled by the batch task, shared with the other auctions of the batch
+    pub is_batch: bool,
+    /// Minimum winning amount, unsold below it
+    pub reserve: Option<Balance>,
+    pub relist: Option<RelistPolicy>,
+    /// Blocks each round stays open, reused when relisting
+    pub duration: BlockHeight,
     bids: UnorderedMap<AccountId, Bid>,
     reveals: TreeMap<Balance, AccountId>,
 }
@@ -134,6 +276,17 @@ pub struct Auction {
 pub struct Registrar {
     auctions: UnorderedMap<AccountId, Auction>,
 
+    // standing offers, title -> offers
+    offers: LookupMap<AccountId, Vec<Offer>>,
+
+    // group bids, auction id -> pools
+    pools: LookupMap<AccountId, Vec<Pool>>,
+
+    // multi-title auctions, lot id -> lot & title -> lot id
+    lots: LookupMap<u64, Lot>,
+    lot_titles: LookupMap<AccountId, u64>,
+    next_lot_id: u64,
+
     // stats
     total_auctions: u64,
     total_canceled_auctions: u64,
@@ -142,7 +295,11 @@ pub struct Registrar {
     // Admin only
     pub escrow: AccountId,
     pub dao: Option<AccountId>,
+    /// Scheduler account, None means auctions are finalized manually
     pub cron: Option<AccountId>,
+    /// Gas for the scheduled finalize call & fee attached when creating the task
+    pub cron_gas: Gas,
+    pub cron_fee: Balance,
     pub paused: bool,
 
     // Base fee will cover things like covering cost of refunding bids in cancel, scheduling cron close, etc
@@ -159,6 +316,7 @@ impl Registrar {
     /// ```bash
     /// near deploy --wasmFile res/registrar.wasm --initFunction new --initArgs '{"escrow": "escrow_account.testnet", "dao": "dao.sputnik.testnet", "cron": "cron.in.testnet"}' --accountId registrar_account.testnet
     /// ```
+    /// Omit "cron" to run without a scheduler, auctions are then finalized by calling "finalize_auction".
     #[init]
     pub fn new(
         escrow: ValidAccountId,
@@ -175,9 +333,16 @@ impl Registrar {
             base_fee: ONE_NEAR / 100_000,
             base_storage_usage: 0,
             auctions: UnorderedMap::new(StorageKeys::Auctions),
+            offers: LookupMap::new(StorageKeys::Offers),
+            pools: LookupMap::new(StorageKeys::Pools),
+            lots: LookupMap::new(StorageKeys::Lots),
+            lot_titles: LookupMap::new(StorageKeys::LotTitles),
+            next_lot_id: 0,
             escrow: escrow.to_string(),
-            dao: Some(dao.unwrap().to_string()),
-            cron: Some(cron.unwrap().to_string()),
+            dao: dao.map(|d| d.to_string()),
+            cron: cron.map(|c| c.to_string()),
+            cron_gas: CRON_TASK_GAS,
+            cron_fee: ONE_NEAR / 100_000,
             total_auctions: 0,
             total_canceled_auctions: 0,
             total_completed_auctions: 0,
@@ -194,11 +359,16 @@ impl Registrar {
         let tmp_account_id = "z".repeat(64);
         let tmp_auction = Auction {
             title: tmp_account_id.clone(),
+            nonce: 0,
             is_blind: true,
             underwriter: Some(tmp_account_id.clone()),
             winner_id: Some(tmp_account_id.clone()),
             close_block: Some(env::block_index()),
             cron_hash: None,
+            is_batch: true,
+            reserve: Some(0),
+            relist: Some(RelistPolicy { retries: 0, decay_bps: 0 }),
+            duration: 0,
             bids: UnorderedMap::new(b"a".to_vec()),
             reveals: TreeMap::new(b"b"),
         };
@@ -213,30 +383,36 @@ impl Registrar {
     /// The underwriter is the original owner or another account that takes ownership in the event
     /// the auction closes with no winner or underwriter wants to claim the account back before auction close.
     /// 
+    /// Optional reserve: auction ends unsold if the highest bid is below it.
+    /// Optional relist: when unsold, a new round opens automatically for the same duration,
+    /// up to `retries` times, with the reserve reduced by `decay_bps` each round.
+    /// Once out of rounds, the title is unlocked and stays in escrow.
+    ///
     /// Defaults:
     /// auction_close_block: 7 Days
     /// auction reveals: 3 Days
     ///
     /// ```bash
-    /// near call _auction_ create '{"title": "account_to_auction.testnet", "auction_close_block": 41000000, "is_blind": true}' --accountId youraccount.testnet
+    /// near call _auction_ create '{"title": "account_to_auction.testnet", "auction_close_block": 41000000, "is_blind": true, "reserve": "10000000000000000000000000", "relist": {"retries": 3, "decay_bps": 1000}}' --accountId youraccount.testnet --gas 300000000000000
     /// ```
     #[payable]
     pub fn create(
         &mut self,
         title: ValidAccountId,
         auction_close_block: Option<BlockHeight>,
-        is_blind: Option<bool>
+        is_blind: Option<bool>,
+        reserve: Option<U128>,
+        relist: Option<RelistPolicy>,
     ) {
-        // Check if there is already an auction with this same matching title
-        // AND if that auction is ongoing (ongoing = current block < closing block)
-        let previous_auction = self.auctions.get(&title.clone().to_string());
-        if previous_auction.is_some() {
-            assert!(
-                env::block_index() > previous_auction.unwrap().close_block.unwrap(),
-                "Auction is already happening"
-            );
+        assert_ne!(title.to_string(), env::signer_account_id(), "Auction cannot be signer name");
+        if let Some(relist) = &relist {
+            assert!(u128::from(relist.decay_bps) <= BPS_DENOMINATOR, "Decay cannot exceed 100%");
         }
 
+        // Check there is no auction (or lot) with this same matching title
+        // that hasn't been finalized yet, closed auctions still hold bids & the escrow lock
+        assert!(!self.is_auction_active(title.to_string()), "Auction is already happening");
+
         // TODO: Check it can cover costs
 
         // Confirm escrow has custody
@@ -250,7 +426,7 @@ impl Registrar {
                 title,
                 env::signer_account_id(),
                 auction_close_block,
-                is_blind,
+                AuctionOptions { is_blind, reserve, relist },
                 &env::current_account_id(),
                 env::attached_deposit(),
                 CREATE_CALLBACK_GAS_FEE,
@@ -266,71 +442,352 @@ impl Registrar {
         title: ValidAccountId,
         signer: AccountId,
         auction_close_block: Option<BlockHeight>,
-        is_blind: Option<bool>,
+        options: AuctionOptions,
         #[callback]
         underwriter: Option<AccountId>,
     ) {
         // Check the signer IS the underwriter
         let owner = underwriter.expect("No underwriter found, abort");
         assert_eq!(&signer, &owner, "Auction can only be started by owner");
+        assert!(!self.lot_titles.contains_key(&title.to_string()), "Title is in a lot");
+        assert!(self.auctions.get(&title.to_string()).is_none(), "Auction is already happening");
+
+        let close_block = self.internal_close_block(auction_close_block);
+        let nonce = self.internal_create_auction(
+            title.to_string(),
+            owner,
+            close_block,
+            options.is_blind.unwrap_or(false),
+            options.reserve.map(|r| r.0),
+            options.relist,
+        );
 
-        let close_block = match auction_close_block {
-            Some(close_block) => {
-                if close_block > env::block_index() { close_block } else { env::block_index() + CLOSE_BLOCK_OFFSET }
-            },
-            None => env::block_index() + CLOSE_BLOCK_OFFSET,
-        };
+        // Lock title in escrow, so it can only be settled by this registrar
+        ext_escrow::lock(
+            title.to_string(),
+            &self.escrow,
+            0,
+            LOCK_GAS_FEE,
+        ).then(
+            ext::lock_callback(
+                title.to_string(),
+                &env::current_account_id(),
+                0,
+                LOCK_CALLBACK_GAS_FEE,
+            )
+        );
 
-        let auction = Auction {
-            title: title.to_string(),
-            is_blind: is_blind.unwrap_or(false),
-            underwriter: Some(owner),
-            winner_id: None,
-            close_block: Some(close_block),
-            cron_hash: None,
-            bids: UnorderedMap::new(StorageKeys::Bids),
-            reveals: TreeMap::new(StorageKeys::Reveals)
-        };
+        self.internal_schedule_finalize(title.to_string(), nonce, close_block);
+    }
 
-        self.auctions.insert(&title.to_string(), &auction);
-        self.total_auctions += 1;
-        log!("New Auction:{}", &title.to_string());
+    /// As many auctions as one cron task can finalize with the configured gas, at least MIN_TASK_BATCH_SIZE
+    fn internal_max_batch_size(&self) -> u64 {
+        (self.cron_gas - FINALIZE_BASE_GAS_FEE) / FINALIZE_GAS_FEE
+    }
 
-        // Schedule the closing of auction with cron.cat
+    /// Schedule the closing of auction with cron.cat, if a scheduler is configured
+    fn internal_schedule_finalize(&self, id: AccountId, nonce: u64, close_block: BlockHeight) {
+        let cron = match self.cron.clone() {
+            Some(cron) => cron,
+            None => return,
+        };
         ext_croncat::create_task(
             env::current_account_id(),
             String::from("finalize_auction"),
             close_block.to_string(),
             Some(false),
             Some(U128::from(0)),
-            Some(140_000_000_000_000), // 140 Tgas
-            Some(json!({ "id": &title.to_string() }).to_string().as_bytes().to_vec()),
-            &String::from("cron.in.testnet"),
-            ONE_NEAR / 100_000,
-            100_000_000_000_000,
+            Some(self.cron_gas),
+            Some(json!({ "id": &id }).to_string().as_bytes().to_vec()),
+            &cron,
+            self.cron_fee,
+            CRON_CREATE_GAS_FEE,
         )
         .then(
             ext::cron_callback(
+                id,
+                nonce,
                 &env::current_account_id(),
                 0,
-                25_000_000_000_000 // 25 Tgas
+                CRON_CALLBACK_GAS_FEE,
             )
         );
     }
 
-    /// Get the task hash, and store in state
+    /// Create Batch:
+    /// Create auctions for many titles at once, with the same close block & type.
+    /// Escrow custody is checked for every title in a single call, titles not owned by the signer
+    /// or already in auction are skipped. Settlement is split over cron tasks, each holding
+    /// as many auctions as it has gas to finalize, a batch is limited to MAX_BATCH_SIZE titles.
+    /// Returns whether an auction was created, for each title in order.
+    ///
+    /// ```bash
+    /// near call _auction_ create_batch '{"titles": ["acme.testnet", "acme-app.testnet"], "auction_close_block": 41000000, "is_blind": false}' --accountId youraccount.testnet --gas 300000000000000
+    /// ```
+    #[payable]
+    pub fn create_batch(
+        &mut self,
+        titles: Vec<ValidAccountId>,
+        auction_close_block: Option<BlockHeight>,
+        is_blind: Option<bool>
+    ) -> Promise {
+        assert!(!titles.is_empty(), "Must include at least one title");
+        assert!(titles.len() as u64 <= MAX_BATCH_SIZE, "Too many titles in batch");
+
+        // Confirm escrow has custody, for all titles at once
+        ext_escrow::get_underwriters(
+            titles.clone(),
+            &self.escrow,
+            0,
+            CHECK_UNDERWRITER_GAS_FEE
+        ).then(
+            ext::create_batch_callback(
+                titles,
+                env::signer_account_id(),
+                auction_close_block,
+                is_blind,
+                &env::current_account_id(),
+                env::attached_deposit(),
+                CREATE_BATCH_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Create Batch Callback
+    /// Returns whether an auction was created, for each title in order
     #[private]
-    pub fn cron_callback(
+    #[payable]
+    pub fn create_batch_callback(
         &mut self,
+        titles: Vec<ValidAccountId>,
+        signer: AccountId,
+        auction_close_block: Option<BlockHeight>,
+        is_blind: Option<bool>,
         #[callback]
-        id: AccountId,
-        #[callback]
-        task_hash: Base64VecU8
-    ) {
+        underwriters: Vec<Option<AccountId>>,
+    ) -> Vec<(AccountId, bool)> {
+        let close_block = self.internal_close_block(auction_close_block);
+        let mut created: Vec<(AccountId, u64)> = Vec::new();
+
+        let results: Vec<(AccountId, bool)> = titles.iter()
+            .zip(underwriters)
+            .map(|(title, underwriter)| {
+                let title = title.to_string();
+                let is_owner = underwriter.as_ref() == Some(&signer);
+                let is_available = !self.is_auction_active(title.clone());
+
+                if is_owner && is_available {
+                    let nonce = self.internal_create_auction(title.clone(), signer.clone(), close_block, is_blind.unwrap_or(false), None, None);
+                    created.push((title.clone(), nonce));
+                    (title, true)
+                } else {
+                    log!("Auction skipped: {}", &title);
+                    (title, false)
+                }
+            })
+            .collect();
+
+        if created.is_empty() {
+            return results;
+        }
+
+        // Lock all titles in escrow, dropping any auction escrow refused to lock
+        let created_titles: Vec<AccountId> = created.iter().map(|(title, _)| title.clone()).collect();
+        ext_escrow::lock_many(
+            created_titles.clone(),
+            &self.escrow,
+            0,
+            LOCK_MANY_GAS_FEE,
+        ).then(
+            ext::lock_many_callback(
+                created_titles,
+                &env::current_account_id(),
+                0,
+                LOCK_CALLBACK_GAS_FEE,
+            )
+        );
+
+        // Schedule the closing of all auctions with cron.cat, each task finalizing as many as its gas allows
+        if let Some(cron) = self.cron.clone() {
+            for chunk in created.chunks(self.internal_max_batch_size() as usize) {
+                let ids: Vec<&AccountId> = chunk.iter().map(|(title, _)| title).collect();
+                ext_croncat::create_task(
+                    env::current_account_id(),
+                    String::from("finalize_auctions"),
+                    close_block.to_string(),
+                    Some(false),
+                    Some(U128::from(0)),
+                    Some(self.cron_gas),
+                    Some(json!({ "ids": ids }).to_string().as_bytes().to_vec()),
+                    &cron,
+                    self.cron_fee,
+                    CRON_CREATE_GAS_FEE,
+                ).then(
+                    ext::cron_batch_callback(
+                        chunk.to_vec(),
+                        &env::current_account_id(),
+                        0,
+                        CRON_CALLBACK_GAS_FEE,
+                    )
+                );
+            }
+        }
+
+        results
+    }
+
+    /// Lock Many Callback
+    /// Removes the auctions escrow refused to lock, refunding any bids already placed
+    #[private]
+    pub fn lock_many_callback(&mut self, titles: Vec<AccountId>) {
+        let locked: Vec<bool> = match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice(&result).unwrap_or_default(),
+            _ => vec![],
+        };
+
+        for (index, title) in titles.iter().enumerate() {
+            if !locked.get(index).copied().unwrap_or(false) {
+                self.internal_remove_unlocked(title);
+            }
+        }
+    }
+
+    /// Defaults the close block when missing or already passed
+    fn internal_close_block(&self, auction_close_block: Option<BlockHeight>) -> BlockHeight {
+        match auction_close_block {
+            Some(close_block) => {
+                if close_block > env::block_index() { close_block } else { env::block_index() + CLOSE_BLOCK_OFFSET }
+            },
+            None => env::block_index() + CLOSE_BLOCK_OFFSET,
+        }
+    }
+
+    /// Stores a new auction for a title, returns its nonce
+    fn internal_create_auction(
+        &mut self,
+        title: AccountId,
+        owner: AccountId,
+        close_block: BlockHeight,
+        is_blind: bool,
+        reserve: Option<Balance>,
+        relist: Option<RelistPolicy>,
+    ) -> u64 {
+        let nonce = self.total_auctions;
+        let auction = Auction {
+            title: title.clone(),
+            nonce,
+            is_blind,
+            underwriter: Some(owner),
+            winner_id: None,
+            close_block: Some(close_block),
+            cron_hash: None,
+            is_batch: false,
+            reserve,
+            relist,
+            duration: close_block.saturating_sub(env::block_index()),
+            // each auction gets its own bids & reveals, so concurrent auctions don't share storage
+            bids: UnorderedMap::new(StorageKeys::AuctionBids { auction_id: nonce }),
+            reveals: TreeMap::new(StorageKeys::AuctionReveals { auction_id: nonce }),
+        };
+
+        self.auctions.insert(&title, &auction);
+        self.total_auctions += 1;
+        log!("New Auction:{}", &title);
+        nonce
+    }
+
+    /// Lock Callback
+    /// If escrow refused the lock (e.g. another registrar holds this title), the auction cannot settle here,
+    /// so remove it and refund any bids already placed
+    #[private]
+    pub fn lock_callback(&mut self, title: AccountId) {
+        if let PromiseResult::Failed = env::promise_result(0) {
+            self.internal_remove_unlocked(&title);
+        }
+    }
+
+    /// Removes an auction escrow would not lock, refunding everything
+    fn internal_remove_unlocked(&mut self, title: &AccountId) {
+        if let Some(auction) = self.auctions.get(title) {
+            for (account_id, Bid { amount, pk: _, precommit: _ }) in auction.bids.iter() {
+                if amount > 0 {
+                    Promise::new(account_id).transfer(amount);
+                }
+            }
+            for pool in self.pools.remove(title).unwrap_or_default().iter() {
+                self.internal_refund_pool(pool, 0);
+            }
+            if !auction.is_batch {
+                self.internal_remove_task(auction.cron_hash.clone());
+            }
+            self.auctions.remove(title);
+            self.total_canceled_auctions += 1;
+        }
+        log!("Lock failed, auction removed: {}", title);
+    }
+
+    /// Get the task hash, and store in state
+    /// If croncat refused the task, the auction stays unscheduled & must be finalized manually.
+    /// The nonce ties the task to the auction it was created for, not a later one for the same title
+    #[private]
+    pub fn cron_callback(&mut self, id: AccountId, nonce: u64) {
+        let task_hash = match self.internal_task_hash() {
+            Some(task_hash) => task_hash,
+            None => {
+                log!("Cron task failed, finalize manually: {}", &id);
+                return;
+            }
+        };
+
         log!("schedule_callback task_hash {:?}", &task_hash);
-        let mut auc = self.auctions.get(&id).expect("Auction doesnt exist");
-        auc.cron_hash = Some(task_hash);
-        self.auctions.insert(&id, &auc);
+        match self.auctions.get(&id) {
+            Some(mut auc) if auc.nonce == nonce => {
+                auc.cron_hash = Some(task_hash);
+                self.auctions.insert(&id, &auc);
+            }
+            _ => {
+                // Auction was removed (or re-created) before the task was confirmed, reclaim its fee
+                self.internal_remove_task(Some(task_hash));
+            }
+        }
+    }
+
+    /// Get the batch task hash, and store it on every auction of the batch still matching its nonce
+    #[private]
+    pub fn cron_batch_callback(&mut self, ids: Vec<(AccountId, u64)>) {
+        let task_hash = match self.internal_task_hash() {
+            Some(task_hash) => task_hash,
+            None => {
+                log!("Cron task failed, finalize manually: {:?}", &ids);
+                return;
+            }
+        };
+
+        let mut is_used = false;
+        for (id, nonce) in ids.iter() {
+            match self.auctions.get(id) {
+                Some(mut auc) if auc.nonce == *nonce => {
+                    auc.cron_hash = Some(task_hash.clone());
+                    auc.is_batch = true;
+                    self.auctions.insert(id, &auc);
+                    is_used = true;
+                }
+                _ => log!("Auction changed before its task was confirmed: {}", id),
+            }
+        }
+
+        // None of the batch is left for the task to finalize, reclaim its fee
+        if !is_used {
+            self.internal_remove_task(Some(task_hash));
+        }
+    }
+
+    /// Task hash returned by croncat "create_task", None if it failed
+    fn internal_task_hash(&self) -> Option<Base64VecU8> {
+        match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice(&result).ok(),
+            _ => None,
+        }
     }
 
     /// Bid:
@@ -345,6 +802,7 @@ impl Registrar {
     /// Optional:
     /// - amount: if no deposit, then MUST be blind bid
     /// - updates: user CAN update bid by calling this fn multiple times
+    /// - pk: if omitted, winner claims the title by calling "claim" on the deed with a key of their choice
     ///
     /// Blind auctions require a commit/reveal setup. In this way, we can create a time boundary to give
     /// auctions a more fair price outcome. Winner is still the highest bid, but with reveal phase outside
@@ -358,13 +816,13 @@ impl Registrar {
     pub fn bid(
         &mut self,
         id: AccountId,
-        pk: Base58PublicKey,
+        pk: Option<Base58PublicKey>,
         commit: Option<Vec<u8>>
     ) {
         let auc = self.auctions.get(&id).expect("Auction doesnt exist");
         assert_ne!(
             auc.underwriter.unwrap(),
-            env::signer_account_id(),
+            env::predecessor_account_id(),
             "Must not be owner of auction"
         );
         assert!(
@@ -390,12 +848,12 @@ impl Registrar {
         // Keep track of how much balance user sent
         let bid = Bid {
             amount: Some(env::attached_deposit()).unwrap_or(0),
-            pk: pk.clone().into(),
+            pk: pk.map(|k| k.into()),
             precommit: Some(commit).unwrap_or(None),
         };
         
         // Update storage
-        auction.bids.insert(&env::signer_account_id(), &bid);
+        auction.bids.insert(&env::predecessor_account_id(), &bid);
         self.auctions.insert(&id, &auction);
     }
 
@@ -411,7 +869,7 @@ impl Registrar {
         let auc = self.auctions.get(&id.to_string()).expect("Auction doesnt exist");
         assert_ne!(
             auc.underwriter.unwrap(),
-            env::signer_account_id(),
+            env::predecessor_account_id(),
             "Must not be owner of auction"
         );
         assert!(
@@ -433,11 +891,11 @@ impl Registrar {
         let reveal_hash: Vec<u8> = bs58::encode(&reveal_str).into_string().as_bytes().to_vec();
 
         // Check that reveal matches precommit
-        let bid = auction.bids.get(&env::signer_account_id()).expect("No bid found");
+        let bid = auction.bids.get(&env::predecessor_account_id()).expect("No bid found");
         assert_eq!(bid.precommit.unwrap(), reveal_hash, "Reveal doesnt match original bid");
 
         // Update storage
-        auction.reveals.insert(&deposit, &env::signer_account_id());
+        auction.reveals.insert(&deposit, &env::predecessor_account_id());
         self.auctions.insert(&id.to_string(), &auction);
     }
 
@@ -467,11 +925,21 @@ impl Registrar {
                 Promise::new(account_id).transfer(amount);
             }
         }
+        for pool in self.pools.remove(&id).unwrap_or_default().iter() {
+            self.internal_refund_pool(pool, 0);
+        }
+
+        // Scheduled finalize is no longer needed, reclaim its fee
+        // Batch tasks stay, the rest of the batch still needs them
+        if !auction.is_batch {
+            self.internal_remove_task(auction.cron_hash);
+        }
 
         // Release from escrow
         ext_escrow::close_escrow(
             id.clone(),
-            env::signer_account_pk(),
+            Base58PublicKey(env::signer_account_pk()),
+            None,
             &self.escrow,
             0,
             CLOSE_ESCROW_GAS_FEE,
@@ -484,15 +952,51 @@ impl Registrar {
 
     /// Finalize Auction:
     /// - award winner the asset, if they were highest bidder
+    /// - winning amount is paid to the underwriter by escrow, once the title transfer succeeds
     /// - all bidders get their bid amounts back, minus fees
+    /// - a pool wins if its total beats every single bid, losing pools are refunded proportionally, minus fees
     ///
     /// NOTE: anyone can call this method, as it is paid by the person wanting the final outcome
-    /// NOTE: cron.cat can also execute this function immediately after the close block
+    /// NOTE: cron.cat can also execute this function immediately after the close block,
+    /// if the auction was already finalized or canceled the cron call is ignored
     ///
     /// ```bash
     /// near call _auction_ finalize_auction '{"id": "auctioned_account.testnet"}' --accountId youraccount.testnet
     /// ```
     pub fn finalize_auction(&mut self, id: AccountId) {
+        if self.cron == Some(env::predecessor_account_id()) && self.auctions.get(&id).is_none() {
+            log!("Auction already settled: {}", &id);
+            return;
+        }
+        self.internal_finalize(id);
+    }
+
+    /// Finalize Auctions:
+    /// Finalize many auctions in one call, e.g. a batch created via "create_batch".
+    /// Auctions already finalized or still open are skipped, processing stops when gas runs low
+    /// so any remaining auctions can be finalized by calling again.
+    ///
+    /// ```bash
+    /// near call _auction_ finalize_auctions '{"ids": ["acme.testnet", "acme-app.testnet"]}' --accountId youraccount.testnet --gas 300000000000000
+    /// ```
+    pub fn finalize_auctions(&mut self, ids: Vec<AccountId>) -> Vec<AccountId> {
+        let mut finalized: Vec<AccountId> = Vec::new();
+        for id in ids {
+            if env::prepaid_gas() - env::used_gas() < FINALIZE_GAS_FEE {
+                log!("Out of gas, finalize remaining auctions again");
+                break;
+            }
+            let is_complete = self.auctions.get(&id)
+                .is_some_and(|a| env::block_index() > a.close_block.unwrap_or(0));
+            if is_complete {
+                self.internal_finalize(id.clone());
+                finalized.push(id);
+            }
+        }
+        finalized
+    }
+
+    fn internal_finalize(&mut self, id: AccountId) {
         // Get auction details
         let auction = self.auctions.get(&id).expect("No auction found");
         assert!(
@@ -501,9 +1005,14 @@ impl Registrar {
         );
         log!("Finalize Auction: {}", &id);
 
+        // Finalized by someone else than cron, remove the pending task
+        if !auction.is_batch && self.cron != Some(env::predecessor_account_id()) {
+            self.internal_remove_task(auction.cron_hash.clone());
+        }
+
         // Find winner, refund others
         let mut winner_id: AccountId = "".to_string();
-        let mut winner_pk: PublicKey = vec![0];
+        let mut winner_pk: Option<PublicKey> = None;
         let mut highest_balance: Balance = 0;
 
         let bids = auction.bids;
@@ -511,11 +1020,13 @@ impl Registrar {
 
         if auction.is_blind {
             // Since reveals is treemap, just sort by highest bid amount (key)
-            let winning_key = reveals.max().expect("No reveals found");
-            let winning_account_id = reveals.get(&winning_key).expect("No reveal account found");
-            let winning_bid = bids.get(&winning_account_id).expect("No bid found for reveal");
-            winner_id = winning_account_id;
-            winner_pk = winning_bid.pk;
+            if let Some(winning_key) = reveals.max() {
+                let winning_account_id = reveals.get(&winning_key).expect("No reveal account found");
+                let winning_bid = bids.get(&winning_account_id).expect("No bid found for reveal");
+                winner_id = winning_account_id;
+                winner_pk = winning_bid.pk;
+                highest_balance = winning_key;
+            }
         } else {
             // Loop to find winner
             for (account_id, Bid { amount, pk, precommit: _}) in bids.iter() {
@@ -527,27 +1038,165 @@ impl Registrar {
             }
         }
 
+        // Pools only join open auctions, winning pool must beat every single bid
+        let pools = self.pools.remove(&id).unwrap_or_default();
+        let winning_pool = pools.iter()
+            .enumerate()
+            .filter(|(_, p)| p.total > highest_balance)
+            .max_by_key(|(_, p)| p.total)
+            .map(|(i, _)| i);
+
+        // Unsold: no bids, or the best one is below reserve
+        let best = winning_pool.map_or(highest_balance, |index| pools[index].total);
+        let is_sold = best > 0 && best >= auction.reserve.unwrap_or(0);
+
         // Loop to return losing funds, minus fees
         for (account_id, Bid { amount, pk: _, precommit: _ }) in bids.iter() {
-            if winner_id != account_id && amount > self.base_fee {
+            if (!is_sold || winning_pool.is_some() || winner_id != account_id) && amount > self.base_fee {
                 Promise::new(account_id).transfer(amount - self.base_fee);
             }
         }
+        for (index, pool) in pools.iter().enumerate() {
+            if !is_sold || Some(index) != winning_pool {
+                self.internal_refund_pool(pool, self.base_fee);
+            }
+        }
 
-        // Release from escrow
-        ext_escrow::close_escrow(
-            id.clone(),
-            winner_pk,
-            &self.escrow,
-            0,
-            CLOSE_ESCROW_GAS_FEE,
-        );
+        if !is_sold {
+            self.auctions.remove(&id);
+            self.internal_relist_or_release(id, auction.underwriter.unwrap_or_default(), auction.is_blind, auction.reserve, auction.relist, auction.duration);
+            return;
+        }
+
+        // Failed transfers of a pooled title refund the controller, who settles with contributors
+        let mut refund_to = winner_id.clone();
+        if let Some(index) = winning_pool {
+            let pool = pools[index].clone();
+            winner_id = pool.recipient.clone().unwrap_or_else(|| pool.controller.clone());
+            winner_pk = pool.pk;
+            highest_balance = pool.total;
+            refund_to = pool.controller;
+            log!("Pool won: {} {}", &id, index);
+        }
+
+        // Release from escrow, paying the underwriter once the title moves
+        let payout = Payout {
+            amount: U128::from(highest_balance),
+            recipient: auction.underwriter.unwrap_or_default(),
+            refund_to: Some(refund_to.clone()),
+        };
+        self.internal_close_escrow(id.clone(), winner_id, winner_pk, Some(payout))
+            .then(
+                ext::close_auction_callback(
+                    id.clone(),
+                    refund_to,
+                    U128::from(highest_balance),
+                    &env::current_account_id(),
+                    0,
+                    CLOSE_AUCTION_CALLBACK_GAS_FEE,
+                )
+            );
 
         // Clear auction storage, since this is over
         self.auctions.remove(&id);
         self.total_completed_auctions += 1;
     }
 
+    /// Close Auction Callback
+    /// If escrow refused to close, the winning amount bounced back here:
+    /// refund the winner (or winning pool controller) & release the title lock
+    #[private]
+    pub fn close_auction_callback(&mut self, title: AccountId, refund_to: AccountId, amount: U128) -> bool {
+        if let PromiseResult::Failed = env::promise_result(0) {
+            Promise::new(refund_to.clone()).transfer(amount.0);
+            self.internal_unlock(title.clone());
+            log!("Auction close failed, refunded: {} {}", &title, &refund_to);
+            return false;
+        }
+        true
+    }
+
+    /// Remove a scheduled croncat task, croncat refunds the remaining task deposit
+    fn internal_remove_task(&self, cron_hash: Option<Base64VecU8>) {
+        if let (Some(cron), Some(task_hash)) = (self.cron.clone(), cron_hash) {
+            ext_croncat::remove_task(
+                task_hash,
+                &cron,
+                0,
+                CRON_REMOVE_GAS_FEE,
+            );
+        }
+    }
+
+    /// Start the next round of an unsold auction if the relist policy allows,
+    /// otherwise unlock the title so it stays in escrow with the underwriter
+    fn internal_relist_or_release(
+        &mut self,
+        id: AccountId,
+        underwriter: AccountId,
+        is_blind: bool,
+        reserve: Option<Balance>,
+        relist: Option<RelistPolicy>,
+        duration: BlockHeight,
+    ) {
+        match relist {
+            Some(RelistPolicy { retries, decay_bps }) if retries > 0 => {
+                let reserve = reserve.map(|r| r / BPS_DENOMINATOR * (BPS_DENOMINATOR - u128::from(decay_bps)));
+                let close_block = env::block_index() + core::cmp::max(duration, 1);
+                let nonce = self.internal_create_auction(
+                    id.clone(),
+                    underwriter,
+                    close_block,
+                    is_blind,
+                    reserve,
+                    Some(RelistPolicy { retries: retries - 1, decay_bps }),
+                );
+                self.internal_schedule_finalize(id.clone(), nonce, close_block);
+                log!("Auction relisted: {} {} rounds left", &id, retries - 1);
+            }
+            _ => {
+                ext_escrow::unlock(
+                    id.clone(),
+                    &self.escrow,
+                    0,
+                    LOCK_GAS_FEE,
+                );
+                self.total_completed_auctions += 1;
+                log!("Auction unsold: {}", &id);
+            }
+        }
+    }
+
+    /// Release a title from escrow, by key or by account for the new owner to claim.
+    /// Payout amount is attached, escrow holds it until the deed confirms the transfer.
+    fn internal_close_escrow(
+        &self,
+        title: AccountId,
+        owner_id: AccountId,
+        owner_pk: Option<PublicKey>,
+        payout: Option<Payout>,
+    ) -> Promise {
+        let deposit = payout.as_ref().map(|p| p.amount.0).unwrap_or(0);
+        match owner_pk {
+            Some(pk) => ext_escrow::close_escrow(
+                title,
+                Base58PublicKey(pk),
+                payout,
+                &self.escrow,
+                deposit,
+                CLOSE_ESCROW_GAS_FEE,
+            ),
+            None => ext_escrow::close_escrow_to_account(
+                title,
+                owner_id,
+                payout,
+                &self.escrow,
+                deposit,
+                CLOSE_ESCROW_GAS_FEE,
+            ),
+        }
+    }
+
     /// Get the current list of auctions
     ///
     /// ```bash
@@ -570,6 +1219,9 @@ impl Registrar {
             "winner_id": auction.winner_id.unwrap_or_default(),
             "title": auction.title,
             "close_block": auction.close_block,
+            "reserve": auction.reserve.map(U128::from),
+            "relist": auction.relist,
+            "cron_hash": auction.cron_hash,
             // TODO: Stringify this
             "bids": auction.bids.len(),
             "reveals": auction.reveals.len(),
@@ -578,6 +1230,31 @@ impl Registrar {
         }).to_string()
     }
 
+    /// Scheduling status of an auction: whether a cron task is scheduled, its task hash,
+    /// whether the task is shared with a batch & the block it runs after.
+    /// Not scheduled means croncat is disabled or refused the task, finalize manually.
+    ///
+    /// ```bash
+    /// near view _auction_ get_schedule '{"id": "account_to_auction.testnet"}'
+    /// ```
+    pub fn get_schedule(&self, id: AccountId) -> Option<(bool, Option<Base64VecU8>, bool, BlockHeight)> {
+        self.auctions.get(&id).map(|auction| (
+            auction.cron_hash.is_some(),
+            auction.cron_hash,
+            auction.is_batch,
+            auction.close_block.unwrap_or(0),
+        ))
+    }
+
+    /// Whether an auction (or lot) exists for this title, and hasn't been finalized or canceled
+    ///
+    /// ```bash
+    /// near view _auction_ is_auction_active '{"id": "account_to_auction.testnet"}'
+    /// ```
+    pub fn is_auction_active(&self, id: AccountId) -> bool {
+        self.auctions.get(&id).is_some() || self.lot_titles.contains_key(&id)
+    }
+
     /// Gets settings
     ///
     /// ```bash
@@ -589,6 +1266,9 @@ impl Registrar {
         StorageUsage,
         AccountId,
         Option<AccountId>,
+        Option<AccountId>,
+        Gas,
+        U128,
     ) {
         (
             self.paused,
@@ -596,6 +1276,9 @@ impl Registrar {
             self.base_storage_usage,
             self.escrow.clone(),
             self.dao.clone(),
+            self.cron.clone(),
+            self.cron_gas,
+            U128::from(self.cron_fee),
         )
     }
 
@@ -605,22 +1288,37 @@ impl Registrar {
     ///
     /// ```bash
     /// near call _auction_ update_settings '{"dao": "dao.sputnik.testnet", "registrar": "registrar.alias.testnet"}' --accountId dao.sputnik.testnet
+    /// near call _auction_ update_settings '{"cron": "cron.in.testnet", "cron_gas": 200000000000000, "cron_fee": "10000000000000000000"}' --accountId dao.sputnik.testnet
+    /// near call _auction_ update_settings '{"disable_cron": true}' --accountId dao.sputnik.testnet
     /// ```
+    #[allow(clippy::too_many_arguments)]
     pub fn update_settings(
         &mut self,
         paused: Option<bool>,
         base_fee: Option<Balance>,
         escrow: Option<AccountId>,
         dao: Option<AccountId>,
+        cron: Option<ValidAccountId>,
+        cron_gas: Option<Gas>,
+        cron_fee: Option<U128>,
+        disable_cron: Option<bool>,
     ) {
         assert!(self.dao.is_some(), "No ownership, cannot change settings");
         assert_eq!(self.dao.clone().unwrap(), env::predecessor_account_id(), "Callee must be dao contract");
         
         // Update each individual setting
-        if paused.is_some() { self.paused = paused.unwrap(); }
-        if base_fee.is_some() { self.base_fee = base_fee.unwrap(); }
-        if escrow.is_some() { self.escrow = escrow.unwrap().to_string(); }
+        if let Some(paused) = paused { self.paused = paused; }
+        if let Some(base_fee) = base_fee { self.base_fee = base_fee; }
+        if let Some(escrow) = escrow { self.escrow = escrow; }
         if dao.is_some() { self.dao = dao; }
+        if let Some(cron) = cron { self.cron = Some(cron.to_string()); }
+        if let Some(cron_gas) = cron_gas {
+            assert!(cron_gas <= MAX_CRON_GAS, "Cron gas exceeds maximum");
+            assert!(cron_gas >= MIN_CRON_GAS, "Cron gas below minimum");
+            self.cron_gas = cron_gas;
+        }
+        if let Some(cron_fee) = cron_fee { self.cron_fee = cron_fee.0; }
+        if disable_cron == Some(true) { self.cron = None; }
     }
 
     /// Returns semver of this contract.
@@ -662,7 +1360,7 @@ impl Registrar {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::test_utils::{accounts, testing_env_with_promise_results, VMContextBuilder};
     use near_sdk::MockedBlockchain;
     use near_sdk::{testing_env};
     use std::convert::TryFrom;
@@ -717,7 +1415,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "Auction is already happening")]
     fn new_auction_item_same_during_auction() {
-        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(true));
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
         testing_env!(context.build());
         // Init with escrow data
         let mut contract = create_blank_registrar();
@@ -729,18 +1427,22 @@ mod tests {
         context.is_view(false);
         testing_env!(context.build());
 
-        // call the contract create twice, so we can panic when the auction item already exists
+        // create twice (first one confirmed by escrow), so we can panic when the auction item already exists
         // AND is active (within the current block height)
-        contract.create(
+        contract.create_callback(
             ValidAccountId::try_from("zanzibar_near").unwrap(),
+            accounts(3).to_string(),
             Some(env::block_index() + 1_000),
-            Some(false)
+            AuctionOptions { is_blind: Some(false), ..Default::default() },
+            Some(accounts(3).to_string()),
         );
         testing_env!(context.build());
         contract.create(
             ValidAccountId::try_from("zanzibar_near").unwrap(),
             Some(1_000),
-            Some(false)
+            Some(false),
+            None,
+            None,
         );
     }
 
@@ -752,12 +1454,15 @@ mod tests {
         // Init with escrow data
         let mut contract = create_blank_registrar();
 
-        // call the contract create twice, so we can panic when the auction item already exists
-        // AND is active (within the current block height)
+        // signer tries to auction its own account
+        let context2 = get_context(accounts(3), ValidAccountId::try_from("bob_near").unwrap(), accounts(3), Some(false));
+        testing_env!(context2.build());
         contract.create(
             ValidAccountId::try_from("bob_near").unwrap(),
             Some(env::block_index() + 1_000),
-            Some(false)
+            Some(false),
+            None,
+            None,
         );
     }
 
@@ -768,11 +1473,13 @@ mod tests {
         // Init with escrow data
         let mut contract = create_blank_registrar();
 
-        // check all the auction item THANGS
-        contract.create(
+        // check all the auction item THANGS, once escrow confirmed custody
+        contract.create_callback(
             ValidAccountId::try_from("zanzibar_near").unwrap(),
+            accounts(3).to_string(),
             Some(env::block_index() + 1_000),
-            Some(false)
+            AuctionOptions { is_blind: Some(false), ..Default::default() },
+            Some(accounts(3).to_string()),
         );
 
         assert_eq!(
@@ -787,4 +1494,751 @@ mod tests {
 
         // assert!("Contract: Returns newly created auction item ID");
     }
-}
+
+    #[test]
+    #[should_panic(expected = "Auction is already happening")]
+    fn new_auction_item_closed_not_finalized() {
+        let mut context = get_context(accounts(3), accounts(2), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        // closed, but bids & lock are only released by finalize
+        context.block_index(env::block_index() + 11);
+        testing_env!(context.build());
+        contract.create(accounts(4), None, None, None, None);
+    }
+
+    #[test]
+    fn make_offer() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+        // replaces previous offer
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+
+        let offers = contract.get_offers(accounts(4));
+        assert_eq!(offers.len(), 1);
+        assert_eq!(offers[0].amount, ONE_NEAR);
+    }
+
+    #[test]
+    #[should_panic(expected = "Offer is below minimum")]
+    fn make_offer_below_minimum() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(1);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+    }
+
+    #[test]
+    fn make_offer_full() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        for i in 0..MAX_OFFERS {
+            let offerer = ValidAccountId::try_from(format!("offerer{}.near", i)).unwrap();
+            let mut context2 = get_context(accounts(3), offerer.clone(), offerer, Some(false));
+            testing_env!(context2.attached_deposit(ONE_NEAR + i as u128).build());
+            contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+        }
+
+        // bigger offer replaces the smallest one
+        let mut context3 = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        testing_env!(context3.attached_deposit(ONE_NEAR * 2).build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+        let offers = contract.get_offers(accounts(4));
+        assert_eq!(offers.len(), MAX_OFFERS);
+        assert!(offers.iter().all(|o| o.offerer != "offerer0.near"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Offer has not expired")]
+    fn withdraw_offer_not_expired() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+        contract.withdraw_offer(accounts(4));
+    }
+
+    #[test]
+    fn accept_offer_callback() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+
+        context = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        context.attached_deposit(ONE_NEAR * 2);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+
+        // underwriter: Acct 2
+        context = get_context(accounts(3), accounts(2), accounts(3), Some(false));
+        testing_env!(context.build());
+        contract.accept_offer_callback(accounts(4), accounts(2).to_string(), Some(accounts(2).to_string()), None);
+        let offers = contract.get_offers(accounts(4));
+        assert_eq!(offers.len(), 1, "Other offers refunded");
+        assert!(offers[0].is_accepted);
+
+        // escrow refused to close, the offerer is refunded
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        assert!(!contract.close_offer_callback(accounts(4).to_string()));
+        assert!(contract.get_offers(accounts(4)).is_empty(), "All offers settled");
+    }
+
+    #[test]
+    #[should_panic(expected = "Offer is being accepted")]
+    fn withdraw_offer_accepted() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1, None);
+
+        context = get_context(accounts(3), accounts(2), accounts(3), Some(false));
+        testing_env!(context.build());
+        contract.accept_offer_callback(accounts(4), accounts(2).to_string(), Some(accounts(2).to_string()), None);
+
+        // expired while escrow is still closing
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.block_index(env::block_index() + 2);
+        testing_env!(context.build());
+        contract.withdraw_offer(accounts(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction is already happening")]
+    fn accept_offer_lot_title() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.create_lot_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(2).to_string())],
+        );
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+
+        context = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context.build());
+        contract.accept_offer(accounts(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Title is locked")]
+    fn accept_offer_callback_locked() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.make_offer(accounts(4), env::block_index() + 1_000, None);
+
+        // escrow holds the lock, e.g. for an active loan
+        context = get_context(accounts(3), accounts(2), accounts(3), Some(false));
+        testing_env!(context.build());
+        contract.accept_offer_callback(accounts(4), accounts(2).to_string(), Some(accounts(2).to_string()), Some("escrow_near".to_string()));
+    }
+
+    #[test]
+    fn lock_callback_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // underwriter: Acct 2
+        contract.create_callback(accounts(4), accounts(2).to_string(), None, AuctionOptions::default(), Some(accounts(2).to_string()));
+        assert!(contract.is_auction_active(accounts(4).to_string()));
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.lock_callback(accounts(4).to_string());
+        assert!(!contract.is_auction_active(accounts(4).to_string()), "Auction must be removed");
+    }
+
+    #[test]
+    fn update_cron_settings() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        assert_eq!(contract.get_settings().5, Some("cron_near".to_string()));
+
+        context = get_context(accounts(3), accounts(3), ValidAccountId::try_from("dao_near").unwrap(), Some(false));
+        testing_env!(context.build());
+        contract.update_settings(None, None, None, None, Some(accounts(5)), Some(200_000_000_000_000), Some(U128::from(1)), None);
+        let settings = contract.get_settings();
+        assert_eq!(settings.5, Some(accounts(5).to_string()));
+        assert_eq!(settings.6, 200_000_000_000_000);
+        assert_eq!(settings.7, U128::from(1));
+
+        // no scheduler, auctions still get created
+        contract.update_settings(None, None, None, None, None, None, None, Some(true));
+        assert_eq!(contract.get_settings().5, None);
+        contract.create_callback(accounts(4), accounts(2).to_string(), None, AuctionOptions::default(), Some(accounts(2).to_string()));
+        assert!(contract.is_auction_active(accounts(4).to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cron gas exceeds maximum")]
+    fn update_cron_gas_too_high() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        context = get_context(accounts(3), accounts(3), ValidAccountId::try_from("dao_near").unwrap(), Some(false));
+        testing_env!(context.build());
+        contract.update_settings(None, None, None, None, None, Some(300_000_000_000_000), None, None);
+    }
+
+    #[test]
+    fn create_callback_gas() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // the callback only gets the gas create attaches, the lock & cron task must fit
+        context.prepaid_gas(CREATE_CALLBACK_GAS_FEE);
+        testing_env!(context.build());
+        contract.create_callback(accounts(4), accounts(2).to_string(), None, AuctionOptions::default(), Some(accounts(2).to_string()));
+        testing_env!(context.build());
+        let lot_id = contract.create_lot_callback(
+            vec![accounts(0), accounts(5)],
+            accounts(2).to_string(),
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(2).to_string())],
+        );
+        context.prepaid_gas(CREATE_BATCH_CALLBACK_GAS_FEE);
+        testing_env!(context.build());
+        contract.create_batch_callback(
+            vec![accounts(1)],
+            accounts(2).to_string(),
+            None,
+            None,
+            vec![Some(accounts(2).to_string())],
+        );
+
+        context.prepaid_gas(CRON_CALLBACK_GAS_FEE);
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 0);
+        // a task for a stale auction is removed within the callback gas
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 1);
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_batch_callback(vec![(accounts(1).to_string(), 0)]);
+
+        // refused locks clean up within their callback gas
+        context.prepaid_gas(LOCK_CALLBACK_GAS_FEE);
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.lock_callback(accounts(4).to_string());
+        context.prepaid_gas(LOCK_LOT_CALLBACK_GAS_FEE);
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"[true,false]".to_vec()));
+        contract.lock_lot_callback(lot_id);
+    }
+
+    #[test]
+    fn cron_callback_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        contract.create_callback(accounts(4), accounts(2).to_string(), None, AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        // auction stays, unscheduled
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.cron_callback(accounts(4).to_string(), 0);
+        let auction = contract.auctions.get(&accounts(4).to_string()).unwrap();
+        assert!(auction.cron_hash.is_none());
+    }
+
+    #[test]
+    fn cron_callback() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(1_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+        assert_eq!(contract.get_schedule(accounts(4).to_string()), Some((false, None, false, 1_000)));
+
+        // croncat returns the task hash
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 0);
+        assert_eq!(
+            contract.get_schedule(accounts(4).to_string()),
+            Some((true, Some(Base64VecU8::from(vec![1, 2, 3])), false, 1_000))
+        );
+    }
+
+    #[test]
+    fn cron_callback_recreated() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // the first auction is removed & the title listed again before its task is confirmed
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(1_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.lock_callback(accounts(4).to_string());
+        testing_env!(context.build());
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(2_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+        contract.create_callback(accounts(5), accounts(2).to_string(), Some(2_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        // late confirmations for the first auction are ignored
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 0);
+        assert_eq!(contract.get_schedule(accounts(4).to_string()), Some((false, None, false, 2_000)));
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_batch_callback(vec![(accounts(4).to_string(), 0), (accounts(5).to_string(), 2)]);
+        assert_eq!(contract.get_schedule(accounts(4).to_string()), Some((false, None, false, 2_000)));
+        assert_eq!(
+            contract.get_schedule(accounts(5).to_string()),
+            Some((true, Some(Base64VecU8::from(vec![1, 2, 3])), true, 2_000))
+        );
+
+        // the current auction still takes its own task
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"BAUG\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 1);
+        assert_eq!(
+            contract.get_schedule(accounts(4).to_string()),
+            Some((true, Some(Base64VecU8::from(vec![4, 5, 6])), false, 2_000))
+        );
+    }
+
+    #[test]
+    fn lock_callback_failed_scheduled() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(1_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 0);
+
+        // escrow refused the lock, auction & its task are removed
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        contract.lock_callback(accounts(4).to_string());
+        assert!(contract.get_schedule(accounts(4).to_string()).is_none());
+        assert!(!contract.is_auction_active(accounts(4).to_string()));
+
+        // a late task confirmation for the removed auction is ignored
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_callback(accounts(4).to_string(), 0);
+        assert!(contract.get_schedule(accounts(4).to_string()).is_none());
+    }
+
+    #[test]
+    fn cron_batch_callback() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(1_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+        contract.create_callback(accounts(5), accounts(2).to_string(), Some(1_000), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"\"AQID\"".to_vec()));
+        contract.cron_batch_callback(vec![(accounts(4).to_string(), 0), (accounts(5).to_string(), 1)]);
+        for id in [accounts(4), accounts(5)].iter() {
+            let (is_scheduled, task_hash, is_batch, _) = contract.get_schedule(id.to_string()).unwrap();
+            assert!(is_scheduled && is_batch);
+            assert_eq!(task_hash, Some(Base64VecU8::from(vec![1, 2, 3])));
+        }
+    }
+
+    #[test]
+    fn finalize_auction() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // underwriter: Acct 2
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.bid(accounts(4).to_string(), None, None);
+
+        context.block_index(env::block_index() + 11);
+        testing_env!(context.build());
+        contract.finalize_auction(accounts(4).to_string());
+        assert!(!contract.is_auction_active(accounts(4).to_string()));
+        assert_eq!(contract.stats().3, 1);
+
+        // escrow refused to close, winner gets the bid back
+        context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        assert!(!contract.close_auction_callback(accounts(4).to_string(), accounts(1).to_string(), U128::from(ONE_NEAR)));
+    }
+
+    #[test]
+    fn finalize_auction_settled_by_cron() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // cron task fires after the auction was already canceled
+        let context = get_context(accounts(3), accounts(3), ValidAccountId::try_from("cron_near").unwrap(), Some(false));
+        testing_env!(context.build());
+        contract.finalize_auction(accounts(4).to_string());
+        assert_eq!(contract.stats().3, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "No auction found")]
+    fn finalize_auction_missing() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.finalize_auction(accounts(4).to_string());
+    }
+
+    #[test]
+    fn create_batch_callback() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // underwriter: Acct 2, Acct 5 is owned by someone else
+        let results = contract.create_batch_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            None,
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(1).to_string())],
+        );
+        assert_eq!(results, vec![(accounts(4).to_string(), true), (accounts(5).to_string(), false)]);
+        assert!(contract.is_auction_active(accounts(4).to_string()));
+        assert!(!contract.is_auction_active(accounts(5).to_string()));
+
+        // escrow refused the lock
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"[false]".to_vec()));
+        contract.lock_many_callback(vec![accounts(4).to_string()]);
+        assert!(!contract.is_auction_active(accounts(4).to_string()), "Auction must be removed");
+    }
+
+    #[test]
+    fn finalize_auctions() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.create_batch_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            Some(env::block_index() + 10),
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(2).to_string())],
+        );
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.bid(accounts(4).to_string(), None, None);
+        contract.bid(accounts(5).to_string(), None, None);
+
+        context.block_index(env::block_index() + 11);
+        context.prepaid_gas(300_000_000_000_000);
+        testing_env!(context.build());
+        let finalized = contract.finalize_auctions(vec![accounts(4).to_string(), accounts(0).to_string()]);
+        assert_eq!(finalized, vec![accounts(4).to_string()]);
+        assert!(contract.is_auction_active(accounts(5).to_string()));
+
+        // the batch task has gas for a full batch
+        context.prepaid_gas(contract.cron_gas);
+        testing_env!(context.build());
+        let finalized = contract.finalize_auctions(vec![accounts(5).to_string()]);
+        assert_eq!(finalized, vec![accounts(5).to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many titles in batch")]
+    fn create_batch_too_large() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        let titles = (0..=MAX_BATCH_SIZE)
+            .map(|i| ValidAccountId::try_from(format!("title{}.near", i)).unwrap())
+            .collect();
+        contract.create_batch(titles, None, None);
+    }
+
+    #[test]
+    fn create_batch_callback_full() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // a full batch fits the callback gas, split over one cron task per 2 auctions
+        context.prepaid_gas(CREATE_BATCH_CALLBACK_GAS_FEE);
+        testing_env!(context.build());
+        let titles: Vec<ValidAccountId> = (0..MAX_BATCH_SIZE)
+            .map(|i| ValidAccountId::try_from(format!("title{}.near", i)).unwrap())
+            .collect();
+        let underwriters = titles.iter().map(|_| Some(accounts(2).to_string())).collect();
+        let results = contract.create_batch_callback(titles, accounts(2).to_string(), None, None, underwriters);
+        assert!(results.iter().all(|(_, created)| *created));
+    }
+
+    #[test]
+    fn finalize_lot() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // underwriter: Acct 2
+        let lot_id = contract.create_lot_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            Some(env::block_index() + 10),
+            vec![Some(accounts(2).to_string()), Some(accounts(2).to_string())],
+        );
+        assert!(contract.is_auction_active(accounts(5).to_string()), "Lot titles count as in auction");
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"[true,true]".to_vec()));
+        contract.lock_lot_callback(lot_id);
+        assert!(contract.get_lot(lot_id).unwrap().3, "Lot must be locked");
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.bid_lot(lot_id);
+
+        context.block_index(env::block_index() + 11);
+        testing_env!(context.build());
+        contract.finalize_lot(lot_id);
+        assert!(contract.get_lot(lot_id).is_none());
+        assert!(!contract.is_auction_active(accounts(4).to_string()));
+
+        // escrow refused the whole lot, winner gets the bid back
+        context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env_with_promise_results(context.build(), PromiseResult::Failed);
+        assert!(!contract.finalize_lot_callback(
+            vec![accounts(4).to_string(), accounts(5).to_string()],
+            accounts(1).to_string(),
+            U128::from(ONE_NEAR),
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Lot titles must all be owned by signer")]
+    fn create_lot_callback_not_owner() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        contract.create_lot_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(1).to_string())],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Lot is not locked in escrow")]
+    fn cancel_lot_not_locked() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        let lot_id = contract.create_lot_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(2).to_string())],
+        );
+
+        // lock_lot_callback has not landed yet
+        let context2 = get_context(accounts(3), accounts(2), accounts(2), Some(false));
+        testing_env!(context2.build());
+        contract.cancel_lot(lot_id);
+    }
+
+    #[test]
+    fn lock_lot_callback_failed() {
+        let context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        let lot_id = contract.create_lot_callback(
+            vec![accounts(4), accounts(5)],
+            accounts(2).to_string(),
+            None,
+            vec![Some(accounts(2).to_string()), Some(accounts(2).to_string())],
+        );
+
+        testing_env_with_promise_results(context.build(), PromiseResult::Successful(b"[true,false]".to_vec()));
+        contract.lock_lot_callback(lot_id);
+        assert!(contract.get_lot(lot_id).is_none(), "Lot must be removed");
+        assert!(!contract.is_auction_active(accounts(4).to_string()));
+    }
+
+    #[test]
+    fn finalize_auction_relist() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // underwriter: Acct 2, reserve 2 NEAR decaying 50% per round
+        contract.create_callback(
+            accounts(4),
+            accounts(2).to_string(),
+            Some(env::block_index() + 10),
+            AuctionOptions {
+                reserve: Some(U128::from(ONE_NEAR * 2)),
+                relist: Some(RelistPolicy { retries: 1, decay_bps: 5_000 }),
+                ..Default::default()
+            },
+            Some(accounts(2).to_string()),
+        );
+
+        // below reserve, so a new round opens
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.bid(accounts(4).to_string(), None, None);
+
+        context.block_index(env::block_index() + 11);
+        testing_env!(context.build());
+        contract.finalize_auction(accounts(4).to_string());
+        let auction = contract.auctions.get(&accounts(4).to_string()).expect("Auction must be relisted");
+        assert_eq!(auction.reserve, Some(ONE_NEAR));
+        assert_eq!(auction.relist.unwrap().retries, 0);
+        assert_eq!(auction.bids.len(), 0);
+        assert_eq!(auction.close_block, Some(env::block_index() + 10));
+
+        // no bids in the last round, title stays in escrow
+        context.block_index(env::block_index() + 11);
+        testing_env!(context.build());
+        contract.finalize_auction(accounts(4).to_string());
+        assert!(!contract.is_auction_active(accounts(4).to_string()));
+        assert_eq!(contract.stats().3, 1);
+    }
+
+    #[test]
+    fn pool_contribute() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+
+        // underwriter: Acct 2
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        let pool_id = contract.create_pool(accounts(4).to_string(), None, Some(accounts(5)));
+
+        context = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        context.attached_deposit(ONE_NEAR * 2);
+        testing_env!(context.build());
+        contract.contribute(accounts(4).to_string(), pool_id);
+
+        let pools = contract.get_pools(accounts(4).to_string());
+        assert_eq!(pools.len(), 1);
+        assert_eq!(pools[0].total, ONE_NEAR * 3);
+        assert_eq!(pools[0].contributions.get(&accounts(0).to_string()), Some(&(ONE_NEAR * 2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Contribution is below minimum")]
+    fn pool_contribute_dust() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        let pool_id = contract.create_pool(accounts(4).to_string(), None, Some(accounts(5)));
+
+        context = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        context.attached_deposit(1);
+        testing_env!(context.build());
+        contract.contribute(accounts(4).to_string(), pool_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many pools for auction")]
+    fn pool_too_many() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        for _ in 0..=MAX_POOLS {
+            contract.create_pool(accounts(4).to_string(), None, Some(accounts(5)));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Pool requires controller key or recipient account")]
+    fn pool_without_controller() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.create_pool(accounts(4).to_string(), None, None);
+    }
+
+    #[test]
+    fn finalize_auction_pool_wins() {
+        let mut context = get_context(accounts(3), accounts(3), accounts(3), Some(false));
+        testing_env!(context.build());
+        let mut contract = create_blank_registrar();
+        contract.create_callback(accounts(4), accounts(2).to_string(), Some(env::block_index() + 10), AuctionOptions::default(), Some(accounts(2).to_string()));
+
+        context = get_context(accounts(3), accounts(1), accounts(1), Some(false));
+        context.attached_deposit(ONE_NEAR * 2);
+        testing_env!(context.build());
+        contract.bid(accounts(4).to_string(), None, None);
+        contract.create_pool(accounts(4).to_string(), None, Some(accounts(5)));
+
+        context = get_context(accounts(3), accounts(0), accounts(0), Some(false));
+        context.attached_deposit(ONE_NEAR);
+        testing_env!(context.build());
+        contract.contribute(accounts(4).to_string(), 0);
+
+        context.block_index(env::block_index() + 11);
+        testing_env!(context.build());
+        contract.finalize_auction(accounts(4).to_string());
+        assert!(!contract.is_auction_active(accounts(4).to_string()));
+        assert!(contract.get_pools(accounts(4).to_string()).is_empty(), "Pools must be cleared");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,285 @@
+use crate::*;
+
+/// Several escrowed titles from one underwriter, sold together through a single open auction
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Lot {
+    pub titles: Vec<AccountId>,
+    pub underwriter: AccountId,
+    pub close_block: BlockHeight,
+    /// True once escrow locked every title
+    pub is_locked: bool,
+    bids: UnorderedMap<AccountId, Bid>,
+}
+
+#[near_bindgen]
+impl Registrar {
+    /// Create Lot:
+    /// Sell several titles owned by the signer as one lot, through a single open auction.
+    /// Every title must be in escrow & owned by the signer, or the lot is not created.
+    /// Returns the new lot id, the lot takes bids once escrow locked every title.
+    ///
+    /// ```bash
+    /// near call _auction_ create_lot '{"titles": ["acme.testnet", "acme-app.testnet", "acmedao.testnet"], "auction_close_block": 41000000}' --accountId youraccount.testnet --gas 300000000000000
+    /// ```
+    #[payable]
+    pub fn create_lot(&mut self, titles: Vec<ValidAccountId>, auction_close_block: Option<BlockHeight>) -> Promise {
+        assert!(titles.len() > 1, "Lot must include more than one title");
+        assert!(titles.len() as u64 <= MAX_LOT_SIZE, "Too many titles in lot");
+
+        // Confirm escrow has custody of every title
+        ext_escrow::get_underwriters(
+            titles.clone(),
+            &self.escrow,
+            0,
+            CHECK_UNDERWRITER_GAS_FEE
+        ).then(
+            ext::create_lot_callback(
+                titles,
+                env::signer_account_id(),
+                auction_close_block,
+                &env::current_account_id(),
+                env::attached_deposit(),
+                CREATE_CALLBACK_GAS_FEE,
+            )
+        )
+    }
+
+    /// Create Lot Callback
+    /// Returns the new lot id
+    #[private]
+    #[payable]
+    pub fn create_lot_callback(
+        &mut self,
+        titles: Vec<ValidAccountId>,
+        signer: AccountId,
+        auction_close_block: Option<BlockHeight>,
+        #[callback]
+        underwriters: Vec<Option<AccountId>>,
+    ) -> u64 {
+        assert_eq!(titles.len(), underwriters.len(), "Missing underwriters");
+        let titles: Vec<AccountId> = titles.iter().map(|t| t.to_string()).collect();
+        for (title, underwriter) in titles.iter().zip(underwriters) {
+            assert_eq!(underwriter, Some(signer.clone()), "Lot titles must all be owned by signer");
+            assert!(!self.is_auction_active(title.clone()), "Auction is already happening");
+        }
+
+        let lot_id = self.next_lot_id;
+        self.next_lot_id += 1;
+        let close_block = self.internal_close_block(auction_close_block);
+        let lot = Lot {
+            titles: titles.clone(),
+            underwriter: signer,
+            close_block,
+            is_locked: false,
+            bids: UnorderedMap::new(StorageKeys::LotBids { lot_id }),
+        };
+        self.lots.insert(&lot_id, &lot);
+        for title in titles.iter() {
+            self.lot_titles.insert(title, &lot_id);
+        }
+        log!("New Lot:{} {:?}", lot_id, &titles);
+
+        // Lock every title in escrow, the lot only stands if all are locked
+        ext_escrow::lock_many(
+            titles,
+            &self.escrow,
+            0,
+            LOCK_MANY_GAS_FEE,
+        ).then(
+            ext::lock_lot_callback(
+                lot_id,
+                &env::current_account_id(),
+                0,
+                LOCK_LOT_CALLBACK_GAS_FEE,
+            )
+        );
+
+        lot_id
+    }
+
+    /// Lock Lot Callback
+    /// If escrow refused any lock, releases the ones it did lock and removes the lot
+    #[private]
+    pub fn lock_lot_callback(&mut self, lot_id: u64) {
+        let locked: Vec<bool> = match env::promise_result(0) {
+            PromiseResult::Successful(result) => near_sdk::serde_json::from_slice(&result).unwrap_or_default(),
+            _ => vec![],
+        };
+        let mut lot = match self.lots.get(&lot_id) {
+            Some(lot) => lot,
+            None => return,
+        };
+
+        if locked.len() == lot.titles.len() && locked.iter().all(|l| *l) {
+            lot.is_locked = true;
+            self.lots.insert(&lot_id, &lot);
+            return;
+        }
+
+        for (title, is_locked) in lot.titles.iter().zip(locked.iter()) {
+            if *is_locked {
+                self.internal_unlock(title.clone());
+            }
+        }
+        self.internal_remove_lot(lot_id, 0, None);
+        log!("Lock failed, lot removed: {}", lot_id);
+    }
+
+    /// Bid Lot:
+    /// Bid on a whole lot, attached deposit is the bid. Bidding again replaces the previous bid, refunding it.
+    /// Winner claims each title by calling "claim" on its deed
+    ///
+    /// ```bash
+    /// near call _auction_ bid_lot '{"lot_id": 0}' --accountId youraccount.testnet --amount 30
+    /// ```
+    #[payable]
+    pub fn bid_lot(&mut self, lot_id: u64) {
+        let mut lot = self.lots.get(&lot_id).expect("Lot doesnt exist");
+        assert!(lot.is_locked, "Lot is not locked in escrow");
+        assert_ne!(lot.underwriter, env::predecessor_account_id(), "Must not be owner of auction");
+        assert!(env::block_index() < lot.close_block, "Must be an active auction");
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "Must submit bid amount of greater than zero");
+
+        let bidder = env::predecessor_account_id();
+        if let Some(previous) = lot.bids.get(&bidder) {
+            Promise::new(bidder.clone()).transfer(previous.amount);
+        }
+        lot.bids.insert(&bidder, &Bid {
+            amount,
+            pk: None,
+            precommit: None,
+        });
+        self.lots.insert(&lot_id, &lot);
+    }
+
+    /// Cancel Lot:
+    /// Underwriter removes the lot before it closes, all bids are refunded & titles unlocked.
+    /// Only once escrow locked the lot, so every lock taken is released.
+    ///
+    /// ```bash
+    /// near call _auction_ cancel_lot '{"lot_id": 0}' --accountId youraccount.testnet
+    /// ```
+    pub fn cancel_lot(&mut self, lot_id: u64) {
+        let lot = self.lots.get(&lot_id).expect("Lot doesnt exist");
+        assert_eq!(lot.underwriter, env::predecessor_account_id(), "Must be owner to cancel auction");
+        assert!(lot.is_locked, "Lot is not locked in escrow");
+        assert!(env::block_index() < lot.close_block, "Auction must not be complete");
+
+        for title in lot.titles.iter() {
+            self.internal_unlock(title.clone());
+        }
+        self.internal_remove_lot(lot_id, 0, None);
+        self.total_canceled_auctions += 1;
+    }
+
+    /// Finalize Lot:
+    /// Highest bid wins every title in the lot, escrow transfers all of them to the winner or none.
+    /// Underwriter is paid by escrow once the titles move, other bids are refunded minus fees.
+    /// If escrow refuses the lot, the winner is refunded & the titles unlocked.
+    /// With no bids, titles are unlocked & stay in escrow.
+    ///
+    /// ```bash
+    /// near call _auction_ finalize_lot '{"lot_id": 0}' --accountId youraccount.testnet --gas 300000000000000
+    /// ```
+    pub fn finalize_lot(&mut self, lot_id: u64) {
+        let lot = self.lots.get(&lot_id).expect("Lot doesnt exist");
+        assert!(env::block_index() > lot.close_block, "Auction must be complete");
+
+        let winner = lot.bids.iter().max_by_key(|(_, bid)| bid.amount);
+        let (winner_id, winning_bid) = match winner {
+            Some(winner) => winner,
+            None => {
+                for title in lot.titles.iter() {
+                    self.internal_unlock(title.clone());
+                }
+                self.internal_remove_lot(lot_id, 0, None);
+                self.total_completed_auctions += 1;
+                return;
+            }
+        };
+
+        // Refund everyone but the winner, minus fees
+        self.internal_remove_lot(lot_id, self.base_fee, Some(winner_id.clone()));
+        self.total_completed_auctions += 1;
+        log!("Finalize Lot: {} to {}", lot_id, &winner_id);
+
+        let payout = Payout {
+            amount: U128::from(winning_bid.amount),
+            recipient: lot.underwriter,
+            refund_to: Some(winner_id.clone()),
+        };
+        ext_escrow::close_escrow_lot(
+            lot.titles.clone(),
+            winner_id.clone(),
+            Some(payout),
+            &self.escrow,
+            winning_bid.amount,
+            CLOSE_LOT_GAS_FEE,
+        ).then(
+            ext::finalize_lot_callback(
+                lot.titles,
+                winner_id,
+                U128::from(winning_bid.amount),
+                &env::current_account_id(),
+                0,
+                FINALIZE_LOT_CALLBACK_GAS_FEE,
+            )
+        );
+    }
+
+    /// Finalize Lot Callback
+    /// If escrow refused to close the lot, the winning bid bounced back here:
+    /// refund the winner & release every title lock
+    #[private]
+    pub fn finalize_lot_callback(&mut self, titles: Vec<AccountId>, winner_id: AccountId, amount: U128) -> bool {
+        if let PromiseResult::Failed = env::promise_result(0) {
+            Promise::new(winner_id.clone()).transfer(amount.0);
+            for title in titles.iter() {
+                self.internal_unlock(title.clone());
+            }
+            log!("Lot close failed, refunded: {:?} {}", &titles, &winner_id);
+            return false;
+        }
+        true
+    }
+
+    /// Get a lot: titles, underwriter, close block, whether escrow locked it & number of bids
+    ///
+    /// ```bash
+    /// near view _auction_ get_lot '{"lot_id": 0}'
+    /// ```
+    pub fn get_lot(&self, lot_id: u64) -> Option<(Vec<AccountId>, AccountId, BlockHeight, bool, u64)> {
+        self.lots.get(&lot_id).map(|lot| (
+            lot.titles,
+            lot.underwriter,
+            lot.close_block,
+            lot.is_locked,
+            lot.bids.len(),
+        ))
+    }
+
+    /// Refunds bids (except the winner) minus fee, then clears the lot & its title index
+    fn internal_remove_lot(&mut self, lot_id: u64, fee: Balance, winner_id: Option<AccountId>) {
+        if let Some(mut lot) = self.lots.remove(&lot_id) {
+            for (account_id, Bid { amount, pk: _, precommit: _ }) in lot.bids.iter() {
+                if Some(&account_id) != winner_id.as_ref() && amount > fee {
+                    Promise::new(account_id).transfer(amount - fee);
+                }
+            }
+            lot.bids.clear();
+            for title in lot.titles.iter() {
+                self.lot_titles.remove(title);
+            }
+        }
+    }
+
+    pub(crate) fn internal_unlock(&self, title: AccountId) {
+        ext_escrow::unlock(
+            title,
+            &self.escrow,
+            0,
+            LOCK_GAS_FEE,
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::*;
+
+/// Standing offer on a title, funds are locked in registrar until accepted or withdrawn
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Offer {
+    pub offerer: AccountId,
+    pub amount: Balance,
+    // when no key is given, the offerer claims the title from the deed by account
+    pub pk: Option<PublicKey>,
+    pub expires_block: BlockHeight,
+    /// Accepted & waiting for escrow to close, cannot be withdrawn or replaced
+    pub is_accepted: bool,
+}
+
+#[near_bindgen]
+impl Registrar {
+    /// Make Offer:
+    /// Lock NEAR against any escrowed title, whether in auction or not.
+    /// Making another offer on the same title replaces the previous one, refunding it.
+    /// Offers are at least 0.1 NEAR, once a title has 10 offers a new one must beat (and refunds) the smallest.
+    /// Funds can only be withdrawn once the offer expires.
+    ///
+    /// ```bash
+    /// near call _auction_ make_offer '{"title": "account_to_buy.testnet", "expires_block": 41000000, "pk": "ed25519:abcd..."}' --accountId youraccount.testnet --amount 10
+    /// ```
+    #[payable]
+    pub fn make_offer(
+        &mut self,
+        title: ValidAccountId,
+        expires_block: BlockHeight,
+        pk: Option<Base58PublicKey>,
+    ) {
+        let amount = env::attached_deposit();
+        assert!(amount >= MIN_OFFER, "Offer is below minimum");
+        assert!(expires_block > env::block_index(), "Offer must expire in the future");
+
+        let offerer = env::predecessor_account_id();
+        let mut offers = self.offers.get(&title.to_string()).unwrap_or_default();
+        assert!(!offers.iter().any(|o| o.is_accepted), "Offer is being accepted");
+
+        // Replace any previous offer from this account
+        if let Some(index) = offers.iter().position(|o| o.offerer == offerer) {
+            let previous = offers.remove(index);
+            Promise::new(offerer.clone()).transfer(previous.amount);
+        }
+
+        // Keep offers bounded, so accepting can refund all of them
+        if offers.len() >= MAX_OFFERS {
+            let (index, smallest) = offers.iter()
+                .enumerate()
+                .min_by_key(|(_, o)| o.amount)
+                .map(|(i, o)| (i, o.amount))
+                .unwrap();
+            assert!(amount > smallest, "Offers are full, must beat the smallest offer");
+            let evicted = offers.remove(index);
+            Promise::new(evicted.offerer).transfer(evicted.amount);
+        }
+
+        offers.push(Offer {
+            offerer,
+            amount,
+            pk: pk.map(|k| k.into()),
+            expires_block,
+            is_accepted: false,
+        });
+        self.offers.insert(&title.to_string(), &offers);
+        log!("New Offer:{} {}", &title, amount);
+    }
+
+    /// Withdraw Offer:
+    /// Refund an expired offer back to the offerer
+    ///
+    /// ```bash
+    /// near call _auction_ withdraw_offer '{"title": "account_to_buy.testnet"}' --accountId youraccount.testnet
+    /// ```
+    pub fn withdraw_offer(&mut self, title: ValidAccountId) {
+        let offerer = env::predecessor_account_id();
+        let mut offers = self.offers.get(&title.to_string()).expect("No offers found");
+        let index = offers.iter().position(|o| o.offerer == offerer).expect("No offer found");
+        assert!(!offers[index].is_accepted, "Offer is being accepted");
+        assert!(env::block_index() > offers[index].expires_block, "Offer has not expired");
+
+        let offer = offers.remove(index);
+        if offers.is_empty() {
+            self.offers.remove(&title.to_string());
+        } else {
+            self.offers.insert(&title.to_string(), &offers);
+        }
+
+        Promise::new(offerer).transfer(offer.amount);
+    }
+
+    /// Accept Offer:
+    /// Underwriter accepts the highest unexpired offer, which closes escrow to the offerer.
+    /// Escrow pays the underwriter once the title moves, all other offers on the title are refunded.
+    /// The accepted offer is kept until escrow answers, if escrow refuses to close it is refunded.
+    /// Title must not be in an auction or lot, or locked in escrow (e.g. by a loan, lease or another registrar).
+    ///
+    /// ```bash
+    /// near call _auction_ accept_offer '{"title": "account_to_buy.testnet"}' --accountId underwriter.testnet --gas 300000000000000
+    /// ```
+    pub fn accept_offer(&mut self, title: ValidAccountId) {
+        assert!(!self.is_auction_active(title.to_string()), "Auction is already happening");
+        let offers = self.offers.get(&title.to_string()).expect("No offers found");
+        assert!(!offers.iter().any(|o| o.is_accepted), "Offer is being accepted");
+
+        // Confirm escrow has custody, signer is underwriter & nothing holds a lock
+        ext_escrow::get_underwriter(
+            title.clone(),
+            &self.escrow,
+            0,
+            CHECK_UNDERWRITER_GAS_FEE
+        ).and(
+            ext_escrow::get_lock(
+                title.clone(),
+                &self.escrow,
+                0,
+                CHECK_UNDERWRITER_GAS_FEE
+            )
+        ).then(
+            ext::accept_offer_callback(
+                title,
+                env::signer_account_id(),
+                &env::current_account_id(),
+                0,
+                ACCEPT_OFFER_CALLBACK_GAS_FEE,
+            )
+        );
+    }
+
+    /// Accept Offer Callback
+    #[private]
+    pub fn accept_offer_callback(
+        &mut self,
+        title: ValidAccountId,
+        signer: AccountId,
+        #[callback]
+        underwriter: Option<AccountId>,
+        #[callback]
+        lock: Option<AccountId>,
+    ) {
+        let owner = underwriter.expect("No underwriter found, abort");
+        assert_eq!(&signer, &owner, "Offer can only be accepted by owner");
+        // escrow would refuse to close, bouncing the payout back here
+        assert!(lock.is_none(), "Title is locked");
+
+        let offers = self.offers.get(&title.to_string()).expect("No offers found");
+        assert!(!offers.iter().any(|o| o.is_accepted), "Offer is being accepted");
+        let mut best = offers.iter()
+            .filter(|o| o.expires_block >= env::block_index())
+            .max_by_key(|o| o.amount)
+            .expect("No active offers found")
+            .clone();
+
+        // Refund everyone else, keep the accepted offer until escrow answers
+        best.is_accepted = true;
+        self.offers.insert(&title.to_string(), &vec![best.clone()]);
+        for offer in offers.iter() {
+            if offer.offerer != best.offerer {
+                Promise::new(offer.offerer.clone()).transfer(offer.amount);
+            }
+        }
+        log!("Offer accepted:{} {}", &title, best.amount);
+
+        // Release from escrow, paying the underwriter once the title moves
+        let payout = Payout {
+            amount: U128::from(best.amount),
+            recipient: owner,
+            refund_to: Some(best.offerer.clone()),
+        };
+        self.internal_close_escrow(title.to_string(), best.offerer, best.pk, Some(payout))
+            .then(
+                ext::close_offer_callback(
+                    title.to_string(),
+                    &env::current_account_id(),
+                    0,
+                    CLOSE_CALLBACK_GAS_FEE,
+                )
+            );
+    }
+
+    /// Close Offer Callback
+    /// Removes the accepted offer once escrow answered. If escrow refused to close,
+    /// the payout bounced back here & is refunded to the offerer.
+    #[private]
+    pub fn close_offer_callback(&mut self, title: AccountId) -> bool {
+        let offer = self.offers.remove(&title)
+            .and_then(|offers| offers.into_iter().find(|o| o.is_accepted))
+            .expect("No accepted offer found");
+
+        if let PromiseResult::Failed = env::promise_result(0) {
+            Promise::new(offer.offerer.clone()).transfer(offer.amount);
+            log!("Offer close failed, refunded: {} {}", &title, &offer.offerer);
+            return false;
+        }
+        true
+    }
+
+    /// Get all offers for a title
+    ///
+    /// ```bash
+    /// near view _auction_ get_offers '{"title": "account_to_buy.testnet"}'
+    /// ```
+    pub fn get_offers(&self, title: ValidAccountId) -> Vec<Offer> {
+        self.offers.get(&title.to_string()).unwrap_or_default()
+    }
+}
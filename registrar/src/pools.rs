@@ -0,0 +1,119 @@
+use crate::*;
+
+/// Group bid on an open auction, funded by many contributors.
+/// A winning pool receives the title under the controller key, or by the recipient account (e.g. a multisig).
+#[derive(BorshDeserialize, BorshSerialize, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Pool {
+    pub controller: AccountId,
+    pub pk: Option<PublicKey>,
+    pub recipient: Option<AccountId>,
+    pub contributions: HashMap<AccountId, Balance>,
+    pub total: Balance,
+}
+
+#[near_bindgen]
+impl Registrar {
+    /// Create Pool:
+    /// Start a group bid on an open auction, the attached deposit is the first contribution.
+    /// Winning title goes to pk, or to the recipient account (e.g. a multisig) which claims it from the deed.
+    /// Returns the pool id, used by others to contribute. At most 5 pools per auction.
+    ///
+    /// ```bash
+    /// near call _auction_ create_pool '{"id": "auctioned_account.testnet", "recipient": "multisig.testnet"}' --accountId youraccount.testnet --amount 5
+    /// ```
+    #[payable]
+    pub fn create_pool(
+        &mut self,
+        id: AccountId,
+        pk: Option<Base58PublicKey>,
+        recipient: Option<ValidAccountId>,
+    ) -> u64 {
+        assert!(pk.is_some() || recipient.is_some(), "Pool requires controller key or recipient account");
+        self.assert_pool_auction(&id);
+
+        let controller = env::predecessor_account_id();
+        let amount = env::attached_deposit();
+        let mut contributions = HashMap::new();
+        contributions.insert(controller.clone(), amount);
+
+        let mut pools = self.pools.get(&id).unwrap_or_default();
+        assert!((pools.len() as u64) < MAX_POOLS, "Too many pools for auction");
+        pools.push(Pool {
+            controller,
+            pk: pk.map(|k| k.into()),
+            recipient: recipient.map(|r| r.to_string()),
+            contributions,
+            total: amount,
+        });
+        self.pools.insert(&id, &pools);
+
+        let pool_id = pools.len() as u64 - 1;
+        log!("New Pool:{} {} {}", &id, pool_id, amount);
+        pool_id
+    }
+
+    /// Contribute:
+    /// Add the attached deposit to a pool, contributing again adds to the previous amount.
+    /// At most 20 contributors per pool.
+    ///
+    /// ```bash
+    /// near call _auction_ contribute '{"id": "auctioned_account.testnet", "pool_id": 0}' --accountId youraccount.testnet --amount 5
+    /// ```
+    #[payable]
+    pub fn contribute(&mut self, id: AccountId, pool_id: u64) {
+        self.assert_pool_auction(&id);
+
+        let amount = env::attached_deposit();
+        let mut pools = self.pools.get(&id).expect("No pools found");
+        let pool = pools.get_mut(pool_id as usize).expect("No pool found");
+        let contributor = env::predecessor_account_id();
+        let previous = pool.contributions.get(&contributor).copied().unwrap_or(0);
+        assert!(
+            previous > 0 || (pool.contributions.len() as u64) < MAX_POOL_CONTRIBUTORS,
+            "Too many contributors for pool"
+        );
+        pool.contributions.insert(contributor, previous + amount);
+        pool.total += amount;
+        self.pools.insert(&id, &pools);
+        log!("Pool Contribution:{} {} {}", &id, pool_id, amount);
+    }
+
+    /// Get all pools for an auction, index is the pool id
+    ///
+    /// ```bash
+    /// near view _auction_ get_pools '{"id": "auctioned_account.testnet"}'
+    /// ```
+    pub fn get_pools(&self, id: AccountId) -> Vec<Pool> {
+        self.pools.get(&id).unwrap_or_default()
+    }
+
+    /// Pools take part in open auctions only, with a minimum deposit, while bidding is open.
+    /// Contributions & pools are bounded, so finalize can refund all of them
+    fn assert_pool_auction(&self, id: &AccountId) {
+        let auction = self.auctions.get(id).expect("Auction doesnt exist");
+        assert!(!auction.is_blind, "Pools not supported for blind auctions");
+        assert_ne!(
+            auction.underwriter.unwrap_or_default(),
+            env::predecessor_account_id(),
+            "Must not be owner of auction"
+        );
+        assert!(
+            env::block_index() < auction.close_block.unwrap(),
+            "Must be an active auction"
+        );
+        assert!(env::attached_deposit() >= MIN_POOL_CONTRIBUTION, "Contribution is below minimum");
+    }
+
+    /// Refund every contributor, sharing the fee proportionally to their contribution
+    pub(crate) fn internal_refund_pool(&self, pool: &Pool, fee: Balance) {
+        let fee = core::cmp::min(fee, pool.total);
+        for (account_id, amount) in pool.contributions.iter() {
+            // parts per million keeps the math within u128
+            let share = fee * (amount * 1_000_000 / pool.total) / 1_000_000;
+            if *amount > share {
+                Promise::new(account_id.clone()).transfer(amount - share);
+            }
+        }
+    }
+}
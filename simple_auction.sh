@@ -18,7 +18,7 @@ export TITLE_ACCOUNT_ID=acct00000001.testnet
 export TITLE_PK=ed25519:6Mzi9dRMSiPWYp7BgLJ2Lj6KPCcs48FwB93NgQ4LKSBo
 
 # Deploy the deed to escrow
-near deploy --wasmFile res/deed.wasm --initFunction new --initArgs '{"escrow": "'$ESCROW_ACCOUNT_ID'", "underwriter": "'$UNDERWRITER_ACCOUNT_ID'"}' --accountId $TITLE_ACCOUNT_ID --initGas 300000000000000 --initDeposit 1
+near deploy --wasmFile res/deed.wasm --initFunction new --initArgs '{"escrow": "'$ESCROW_ACCOUNT_ID'", "underwriter": "'$UNDERWRITER_ACCOUNT_ID'", "keys": ["'$TITLE_PK'"]}' --accountId $TITLE_ACCOUNT_ID --initGas 300000000000000 --initDeposit 1
 
 # Check escrow has it
 near view $ESCROW_ACCOUNT_ID in_escrow '{"title": "'$TITLE_ACCOUNT_ID'"}'